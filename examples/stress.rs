@@ -0,0 +1,77 @@
+//! Many annotated spans in a single text block, to measure the cost of placing ruby.
+//!
+//! The text sways every frame, so that all of its ruby is placed again each frame. Frame times
+//! are logged to the console.
+
+use bevy::{
+    asset::UnapprovedPathMode,
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+};
+
+use bevy_text_furigana::*;
+
+/// Number of spans with ruby.
+const SPANS: usize = 200;
+
+#[derive(Component)]
+struct Swaying;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            unapproved_path_mode: UnapprovedPathMode::Allow,
+            ..default()
+        }))
+        .add_plugins((
+            FuriganaPlugin,
+            FrameTimeDiagnosticsPlugin::default(),
+            LogDiagnosticsPlugin::default(),
+        ))
+        .add_systems(Startup, startup)
+        .add_systems(Update, sway)
+        .run();
+}
+
+fn startup(mut commands: Commands, assets: Res<AssetServer>) {
+    let font = assets.load("https://github.com/googlefonts/zen-marugothic/raw/refs/heads/main/fonts/ttf/ZenMaruGothic-Regular.ttf");
+
+    let text_font = TextFont {
+        font,
+        font_size: 20.0,
+        ..default()
+    };
+
+    let words = [
+        ("漢字", "かんじ"),
+        ("振仮名", "ふりがな"),
+        ("猫", "ねこ"),
+        ("名前", "なまえ"),
+    ];
+
+    commands
+        .spawn((
+            Text::default(),
+            text_font.clone(),
+            Node {
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            Swaying,
+        ))
+        .with_children(|parent| {
+            for (base, rt) in words.into_iter().cycle().take(SPANS) {
+                parent.spawn((TextSpan::new(base), text_font.clone(), Ruby::new(rt)));
+                parent.spawn((TextSpan::new("と"), text_font.clone()));
+            }
+        });
+
+    commands.spawn(Camera2d);
+}
+
+fn sway(time: Res<Time>, mut query: Query<&mut UiTransform, With<Swaying>>) {
+    for mut transform in &mut query {
+        transform.translation = Val2::px(10.0 * time.elapsed_secs().sin(), 0.0);
+    }
+}
@@ -64,10 +64,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
     commands.spawn((
         Text2d("Lorem ipsum dolor sit amet".into()),
         text_font.clone(),
-        Ruby {
-            rt: "consectetur adipiscing elit".into(),
-            ..default()
-        },
+        Ruby::new("consectetur adipiscing elit"),
         Transform::from_translation(Vec3::new(0.0, 100.0, 0.0)),
     ));
 
@@ -76,10 +73,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
         .spawn((
             Text2d::new("年紀"),
             text_font.clone(),
-            Ruby {
-                rt: "とし".into(),
-                ..default()
-            },
+            Ruby::new("とし"),
             Transform::from_translation(Vec3::new(0.0, 200.0, 0.0)),
         ))
         .with_children(|parent| {
@@ -132,11 +126,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
         Text2d("超電磁砲".into()),
         TextBackgroundColor(bevy::color::palettes::css::ORANGE.into()),
         text_font.clone(),
-        Ruby {
-            rt: "レールガン".into(),
-            position: RubyPosition::Under,
-            ..default()
-        },
+        Ruby::new("レールガン").under(),
         Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
         TextRotator(0.0),
     ));
@@ -152,31 +142,19 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
                 TextSpan::new("幻花翁"),
                 text_font.clone(),
                 TextColor(bevy::color::palettes::css::GREEN.into()),
-                Ruby {
-                    rt: "げんくわおう".into(),
-                    align: RubyAlign::Start,
-                    ..default()
-                },
+                Ruby::new("げんくわおう").align(RubyAlign::Start),
             ),
             (TextSpan::new("、"), text_font.clone()),
             (
                 TextSpan::new("望蜀生"),
                 text_font.clone(),
-                Ruby {
-                    rt: "ぼうしよくせい".into(),
-                    align: RubyAlign::Center,
-                    ..default()
-                },
+                Ruby::new("ぼうしよくせい").align(RubyAlign::Center),
             ),
             (TextSpan::new("、"), text_font.clone()),
             (
                 TextSpan::new("玄川子"),
                 text_font.clone(),
-                Ruby {
-                    rt: "げんせんし".into(),
-                    align: RubyAlign::End,
-                    ..default()
-                },
+                Ruby::new("げんせんし").align(RubyAlign::End),
             ),
         ],
     ));
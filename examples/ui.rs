@@ -84,14 +84,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
         ))
         .with_children(|parent| {
             parent
-                .spawn((
-                    Text("年紀".into()),
-                    text_font.clone(),
-                    Ruby {
-                        rt: "とし".into(),
-                        ..default()
-                    },
-                ))
+                .spawn((Text("年紀".into()), text_font.clone(), Ruby::new("とし")))
                 .with_children(|parent| {
                     ruby_spans(
                         parent,
@@ -140,10 +133,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
             parent.spawn((
                 Text::new("Lorem ipsum dolor sit amet\n"),
                 text_font.clone(),
-                Ruby {
-                    rt: "consectetur adipiscing elit".into(),
-                    ..default()
-                },
+                Ruby::new("consectetur adipiscing elit"),
                 ToggleDisplay,
             ));
 
@@ -153,12 +143,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
                 children![(
                     TextSpan::new("超電磁砲"),
                     text_font.clone(),
-                    Ruby {
-                        rt: "レールガン".into(),
-                        position: RubyPosition::Under,
-                        font_size_scale: 0.8,
-                        ..default()
-                    },
+                    Ruby::new("レールガン").under().scale(0.8),
                 )],
             ));
 
@@ -184,45 +169,29 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
                             (
                                 TextSpan::new("幻花翁"),
                                 text_font.clone(),
-                                Ruby {
-                                    rt: "げんくわおう".into(),
-                                    align: RubyAlign::Start,
-                                    ..default()
-                                },
+                                Ruby::new("げんくわおう").align(RubyAlign::Start),
                             ),
                             (TextSpan::new("、\n"), text_font.clone()),
                             (
                                 TextSpan::new("望蜀生"),
                                 text_font.clone(),
-                                Ruby {
-                                    rt: "ぼうしよくせい".into(),
-                                    align: RubyAlign::Center,
-                                    ..default()
-                                },
+                                Ruby::new("ぼうしよくせい").align(RubyAlign::Center),
                             ),
                             (TextSpan::new("、\n"), text_font.clone()),
                             (
                                 TextSpan::new("玄川子"),
                                 text_font.clone(),
-                                Ruby {
-                                    rt: "げんせんし".into(),
-                                    align: RubyAlign::End,
-                                    ..default()
-                                },
+                                Ruby::new("げんせんし").align(RubyAlign::End),
                             ),
                         ],
                     ));
 
-                    // FIXME: these are broken
                     parent
                         .spawn((
                             Text::new("品川\n"),
                             text_font.clone(),
                             TextColor(bevy::color::palettes::css::GREEN.into()),
-                            Ruby {
-                                rt: "しながは".into(),
-                                ..default()
-                            },
+                            Ruby::new("しながは"),
                             Node {
                                 margin: UiRect::left(px(20)),
                                 ..default()
@@ -231,10 +200,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
                         .with_child((
                             TextSpan::new("下末吉村"),
                             text_font.clone(),
-                            Ruby {
-                                rt: "しもすゑよしむら".into(),
-                                ..default()
-                            },
+                            Ruby::new("しもすゑよしむら"),
                         ));
                 });
         });
@@ -242,10 +208,7 @@ fn startup(mut commands: Commands, assets: Res<AssetServer>) {
     commands
         .spawn((
             Text::new("ルート"),
-            Ruby {
-                rt: "Root".into(),
-                ..default()
-            },
+            Ruby::new("Root"),
             Node {
                 position_type: PositionType::Absolute,
                 top: Val::Px(150.0),
@@ -0,0 +1,67 @@
+//! Ruby beside vertical (tate-gaki) text.
+//!
+//! Bevy has no vertical text layout yet, so each column is written one character per line.
+
+use bevy::{asset::UnapprovedPathMode, prelude::*, sprite::Anchor};
+
+use bevy_text_furigana::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            unapproved_path_mode: UnapprovedPathMode::Allow,
+            ..default()
+        }))
+        .add_plugins(FuriganaPlugin)
+        .add_systems(Startup, startup)
+        .run();
+}
+
+fn startup(mut commands: Commands, assets: Res<AssetServer>) {
+    let font = assets.load("https://github.com/googlefonts/zen-marugothic/raw/refs/heads/main/fonts/ttf/ZenMaruGothic-Regular.ttf");
+
+    let text_font = TextFont {
+        font: font.clone(),
+        font_size: 32.0,
+        ..default()
+    };
+
+    // Sampled from 吾輩は猫である, with columns laid out from right to left
+    let columns = [
+        ("吾輩《わがはい》は猫《ねこ》である。", RubyPosition::Right),
+        ("名前《なまえ》はまだ無《な》い。", RubyPosition::Right),
+        ("どこで生《うま》れたか", RubyPosition::Left),
+        ("頓《とん》と見当《けんとう》がつかぬ。", RubyPosition::Left),
+    ];
+
+    for (i, (column, position)) in columns.into_iter().enumerate() {
+        commands
+            .spawn((
+                Text2d::default(),
+                text_font.clone(),
+                Anchor::TOP_CENTER,
+                Transform::from_translation(Vec3::new(150.0 - 100.0 * i as f32, 250.0, 0.0)),
+            ))
+            .with_children(|parent| {
+                for (text, rt) in parse_aozora(column) {
+                    let mut span = parent.spawn((TextSpan(vertical(&text)), text_font.clone()));
+                    if let Some(rt) = rt {
+                        span.insert(Ruby {
+                            rt,
+                            position,
+                            // The sides of a column are right at the glyphs, with no leading
+                            vertical_align: RubyVerticalAlign::Outside,
+                            ..default()
+                        });
+                    }
+                }
+            });
+    }
+
+    commands.spawn((Camera2d, Camera::default()));
+}
+
+/// Writes `text` one character per line.
+fn vertical(text: &str) -> String {
+    text.chars().map(|c| format!("{c}\n")).collect()
+}
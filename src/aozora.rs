@@ -0,0 +1,287 @@
+use bevy::{ecs::relationship::RelatedSpawnerCommands, prelude::*};
+
+use crate::Ruby;
+
+/// Marks the start of the base text of a ruby.
+const BASE_START: char = '｜';
+const RUBY_START: char = '《';
+const RUBY_END: char = '》';
+/// Escapes the next character, so that e.g. `\《` is kept as a literal `《`.
+const ESCAPE: char = '\\';
+/// Notes Aozora Bunko uses for literal `《` and `》`.
+const LITERAL_NOTES: [(&str, char); 2] = [
+    ("※［＃始め二重山括弧、1-1-52］", RUBY_START),
+    ("※［＃終わり二重山括弧、1-1-53］", RUBY_END),
+];
+
+/// Parses text with Aozora Bunko ruby notation into `(base, ruby)` segments.
+///
+/// Ruby is written in `《》` right after its base text, e.g. `漢字《かんじ》`. Without `｜`
+/// marking the start of the base (as in `｜漢字《かんじ》`), the base is the run of kanji right
+/// before `《`. Literal `《`, `》` and `｜` can be written escaped with `\` or as the notes Aozora
+/// Bunko uses (e.g. `※［＃始め二重山括弧、1-1-52］`).
+///
+/// Unannotated text is returned as `(text, None)` segments, and unclosed or empty `《》` is
+/// kept as is.
+pub fn parse_aozora(text: &str) -> Vec<(String, Option<String>)> {
+    let mut segments = vec![];
+    // Unannotated text so far, and the position of a pending `｜` in it
+    let mut plain = String::new();
+    let mut base_start = None;
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(&(note, literal)) = LITERAL_NOTES
+            .iter()
+            .find(|(note, _)| rest.starts_with(note))
+        {
+            plain.push(literal);
+            rest = &rest[note.len()..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+
+        match c {
+            ESCAPE => {
+                if let Some(escaped) = rest.chars().next() {
+                    plain.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                } else {
+                    plain.push(ESCAPE);
+                }
+            }
+            BASE_START => {
+                base_start = Some(plain.len());
+                plain.push(BASE_START);
+            }
+            RUBY_START => {
+                let ruby = rest.split_once(RUBY_END).map(|(ruby, _)| ruby);
+                let base_range = match base_start {
+                    Some(start) => Some((start, start + BASE_START.len_utf8())),
+                    None => trailing_kanji_start(&plain).map(|start| (start, start)),
+                };
+                let (Some(ruby), Some((marker, base_from))) = (ruby, base_range) else {
+                    plain.push(RUBY_START);
+                    continue;
+                };
+                if ruby.is_empty() || base_from == plain.len() {
+                    plain.push(RUBY_START);
+                    continue;
+                }
+
+                let base = plain[base_from..].to_string();
+                plain.truncate(marker);
+                if !plain.is_empty() {
+                    segments.push((core::mem::take(&mut plain), None));
+                }
+                segments.push((base, Some(ruby.to_string())));
+                rest = &rest[ruby.len() + RUBY_END.len_utf8()..];
+                base_start = None;
+            }
+            _ => plain.push(c),
+        }
+    }
+
+    if !plain.is_empty() {
+        segments.push((plain, None));
+    }
+    segments
+}
+
+/// Returns the byte index where the run of kanji at the end of `text` starts, if any.
+fn trailing_kanji_start(text: &str) -> Option<usize> {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_kanji(c))
+        .last()?
+        .0;
+    Some(start)
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{3FFFF}'
+        // 々, 〆, 〇, ヵ, ヶ
+        | '\u{3005}'..='\u{3007}'
+        | '\u{30F5}'..='\u{30F6}'
+    )
+}
+
+/// Spawns a `TextSpan` with `span` for each of `segments` (e.g. from [`parse_aozora`]), with
+/// [`Ruby`] on the annotated ones.
+pub fn spawn_furigana_spans(
+    spawner: &mut RelatedSpawnerCommands<ChildOf>,
+    segments: impl IntoIterator<Item = (String, Option<String>)>,
+    span: impl Bundle + Clone,
+) {
+    for (text, rt) in segments {
+        let mut span = spawner.spawn((TextSpan(text), span.clone()));
+        if let Some(rt) = rt {
+            span.insert(Ruby::new(rt));
+        }
+    }
+}
+
+/// Spawns annotated `TextSpan`s under `parent` (a [`RelatedSpawnerCommands`], e.g. in
+/// `with_children`) from `(base, reading)` pairs, with `span` (e.g. a `TextFont`) on each, like
+/// [`spawn_furigana_spans`].
+///
+/// The reading is a string (`&str` or `String`), an `Option` of one, or `None` for unannotated
+/// text, e.g. `annotate!(parent, text_font.clone(), [("漢字", "かんじ"), ("です", None)])`.
+#[macro_export]
+macro_rules! annotate {
+    (@reading None) => {
+        ::core::option::Option::<::std::string::String>::None
+    };
+    (@reading $reading:expr) => {
+        $crate::IntoReading::into_reading($reading)
+    };
+    ($parent:expr, $span:expr, [$(($base:expr, $($reading:tt)+)),* $(,)?]) => {
+        $crate::spawn_furigana_spans(
+            $parent,
+            [$((
+                ::std::string::String::from($base),
+                $crate::annotate!(@reading $($reading)+),
+            )),*],
+            $span,
+        )
+    };
+}
+
+/// A reading for [`annotate!`]: a string, or an optional one.
+#[doc(hidden)]
+pub trait IntoReading {
+    fn into_reading(self) -> Option<String>;
+}
+
+impl IntoReading for &str {
+    fn into_reading(self) -> Option<String> {
+        Some(self.to_owned())
+    }
+}
+
+impl IntoReading for String {
+    fn into_reading(self) -> Option<String> {
+        Some(self)
+    }
+}
+
+impl IntoReading for &String {
+    fn into_reading(self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl<T: Into<String>> IntoReading for Option<T> {
+    fn into_reading(self) -> Option<String> {
+        self.map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(segments: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        segments
+            .iter()
+            .map(|&(base, ruby)| (base.to_string(), ruby.map(str::to_string)))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_aozora() {
+        assert_eq!(
+            parse_aozora("すべての漢字《かんじ》に｜振り仮名《ふりがな》を"),
+            segments(&[
+                ("すべての", None),
+                ("漢字", Some("かんじ")),
+                ("に", None),
+                ("振り仮名", Some("ふりがな")),
+                ("を", None),
+            ])
+        );
+        // Kana right before `《` without `｜`
+        assert_eq!(
+            parse_aozora("お前様《まえさん》"),
+            segments(&[("お", None), ("前様", Some("まえさん"))])
+        );
+        assert_eq!(
+            parse_aozora("ひらがな《ruby》"),
+            segments(&[("ひらがな《ruby》", None)])
+        );
+    }
+
+    #[test]
+    fn test_parse_aozora_literals() {
+        assert_eq!(parse_aozora(r"\《漢字\》"), segments(&[("《漢字》", None)]));
+        assert_eq!(
+            parse_aozora("※［＃始め二重山括弧、1-1-52］本※［＃終わり二重山括弧、1-1-53］"),
+            segments(&[("《本》", None)])
+        );
+        // Unclosed, empty, and stray notation
+        assert_eq!(
+            parse_aozora("漢字《かんじ"),
+            segments(&[("漢字《かんじ", None)])
+        );
+        assert_eq!(parse_aozora("漢字《》"), segments(&[("漢字《》", None)]));
+        assert_eq!(
+            parse_aozora("｜a｜b《び》"),
+            segments(&[("｜a", None), ("b", Some("び"))])
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let text_font = TextFont::from_font_size(30.0);
+        let mut commands = app.world_mut().commands();
+        let text_entity = commands
+            .spawn(Text::default())
+            .with_children(|parent| {
+                crate::annotate!(
+                    parent,
+                    text_font.clone(),
+                    [
+                        ("漢字", "かんじ"),
+                        ("です", None),
+                        (String::from("仮名"), format!("か{}", "な")),
+                        ("猫", Some(String::from("ねこ"))),
+                        ("犬", Option::<String>::None),
+                    ]
+                );
+            })
+            .id();
+        app.world_mut().flush();
+
+        let world = app.world();
+        let spans = world
+            .get::<Children>(text_entity)
+            .unwrap()
+            .iter()
+            .map(|child| {
+                assert_eq!(world.get::<TextFont>(child), Some(&text_font));
+                (
+                    world.get::<TextSpan>(child).unwrap().0.as_str(),
+                    world.get::<Ruby>(child).map(|ruby| ruby.rt.as_str()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("漢字", Some("かんじ")),
+                ("です", None),
+                ("仮名", Some("かな")),
+                ("猫", Some("ねこ")),
+                ("犬", None),
+            ]
+        );
+    }
+}
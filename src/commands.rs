@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{Ruby, RubyAlign};
+
+/// Extension trait for [`Commands`] to modify the [`Ruby`] of a base text entity.
+pub trait RubyCommandsExt {
+    /// Sets [`Ruby::align`] of `base`. Does nothing if `base` has no [`Ruby`].
+    fn set_ruby_align(&mut self, base: Entity, align: RubyAlign);
+}
+
+impl RubyCommandsExt for Commands<'_, '_> {
+    fn set_ruby_align(&mut self, base: Entity, align: RubyAlign) {
+        self.queue(move |world: &mut World| {
+            if let Some(mut ruby) = world.get_mut::<Ruby>(base) {
+                ruby.align = align;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn test_set_ruby_align() {
+        let mut world = World::new();
+        let base = world.spawn(Ruby::new("ruby")).id();
+
+        world
+            .run_system_once(move |mut commands: Commands| {
+                commands.set_ruby_align(base, RubyAlign::End);
+            })
+            .unwrap();
+
+        assert_eq!(world.get::<Ruby>(base).unwrap().align, RubyAlign::End);
+    }
+}
@@ -0,0 +1,78 @@
+use ttf_parser::{Face, Language, PlatformId, name_id};
+
+/// Parses the first face of a font (TrueType/OpenType, or the first font of a collection).
+fn face(data: &[u8]) -> Option<Face<'_>> {
+    Face::parse(data, 0).ok()
+}
+
+/// Reads the cap height of a font from its `OS/2` table, in ems.
+pub(crate) fn cap_height(data: &[u8]) -> Option<f32> {
+    let face = face(data)?;
+    let cap_height = face.capital_height()?;
+    (cap_height > 0).then(|| cap_height as f32 / face.units_per_em() as f32)
+}
+
+/// Reads the descender of a font (the depth below the baseline), in ems.
+pub(crate) fn descender(data: &[u8]) -> Option<f32> {
+    let face = face(data)?;
+    let descender = face.descender();
+    (descender < 0).then(|| -(descender as f32) / face.units_per_em() as f32)
+}
+
+/// Reads the family name of a font from its `name` table, preferring the typographic family name.
+///
+/// Names in US English are taken first, so that a font listing its family in several languages
+/// always gives the same one, falling back to the first Windows and then any Unicode name.
+pub(crate) fn family_name(data: &[u8]) -> Option<String> {
+    let face = face(data)?;
+    [name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY]
+        .into_iter()
+        .find_map(|name_id| {
+            let names = face
+                .names()
+                .into_iter()
+                .filter(move |name| name.name_id == name_id && name.is_unicode());
+            names
+                .clone()
+                .find(|name| name.language() == Language::English_UnitedStates)
+                .or_else(|| {
+                    names
+                        .clone()
+                        .find(|name| name.platform_id == PlatformId::Windows)
+                })
+                .or_else(|| names.clone().next())
+        })
+        .and_then(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_height() {
+        let cap_height = cap_height(bevy::text::DEFAULT_FONT_DATA).unwrap();
+        assert!(cap_height > 0.5 && cap_height < 1.0, "{cap_height}");
+        assert_eq!(super::cap_height(&[]), None);
+        assert_eq!(
+            super::cap_height(&bevy::text::DEFAULT_FONT_DATA[..60]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_family_name() {
+        assert_eq!(
+            family_name(bevy::text::DEFAULT_FONT_DATA).as_deref(),
+            Some("Fira Mono")
+        );
+        assert_eq!(family_name(&[]), None);
+    }
+
+    #[test]
+    fn test_descender() {
+        let descender = descender(bevy::text::DEFAULT_FONT_DATA).unwrap();
+        assert!(descender > 0.0 && descender < 0.5, "{descender}");
+        assert_eq!(super::descender(&[]), None);
+    }
+}
@@ -0,0 +1,100 @@
+use core::fmt::Write;
+
+use bevy::prelude::*;
+
+use crate::{Ruby, RubyAlign, RubyPosition, reading::text_sections};
+
+/// Serializes the text tree under `root` (a `Text` or `Text2d` with its `TextSpan` descendants)
+/// into HTML, with [`Ruby`] annotations as `<ruby>` elements.
+pub fn to_ruby_html(world: &World, root: Entity) -> String {
+    let mut html = String::new();
+    for (text, ruby) in text_sections(world, root) {
+        write_section(&mut html, text, ruby);
+    }
+    html
+}
+
+fn write_section(html: &mut String, text: &str, ruby: Option<&Ruby>) {
+    let Some(ruby) = ruby else {
+        write_escaped(html, text);
+        return;
+    };
+
+    let mut styles = vec![];
+    if ruby.position != RubyPosition::default() {
+        styles.push(match ruby.position {
+            RubyPosition::Over => "ruby-position: over",
+            RubyPosition::Under => "ruby-position: under",
+            // Over and under are the right and left of vertical text
+            RubyPosition::Right => "ruby-position: over",
+            RubyPosition::Left => "ruby-position: under",
+        });
+    }
+    if ruby.align != RubyAlign::default() {
+        styles.push(match ruby.align {
+            RubyAlign::Start => "ruby-align: start",
+            RubyAlign::Center => "ruby-align: center",
+            RubyAlign::End => "ruby-align: end",
+            RubyAlign::Justify => "ruby-align: space-between",
+            RubyAlign::Distribute => "ruby-align: space-around",
+        });
+    }
+
+    if styles.is_empty() {
+        html.push_str("<ruby>");
+    } else {
+        let _ = write!(html, "<ruby style=\"{}\">", styles.join("; "));
+    }
+    write_escaped(html, text);
+    html.push_str("<rt>");
+    write_escaped(html, &ruby.rt);
+    html.push_str("</rt></ruby>");
+}
+
+fn write_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\n' => html.push_str("<br>"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ruby_html() {
+        let mut world = World::new();
+        let root = world
+            .spawn((
+                Text::new("私"),
+                Ruby::new("わたし"),
+                children![
+                    TextSpan::new("は<"),
+                    (
+                        TextSpan::new("超電磁砲"),
+                        Ruby {
+                            rt: "レールガン".into(),
+                            position: RubyPosition::Under,
+                            align: RubyAlign::Start,
+                            ..default()
+                        },
+                    ),
+                    TextSpan::new("\n"),
+                ],
+            ))
+            .id();
+
+        assert_eq!(
+            to_ruby_html(&world, root),
+            "<ruby>私<rt>わたし</rt></ruby>は&lt;\
+             <ruby style=\"ruby-position: under; ruby-align: start\">超電磁砲<rt>レールガン</rt></ruby><br>"
+        );
+    }
+}
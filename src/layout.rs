@@ -0,0 +1,1602 @@
+use std::borrow::Cow;
+
+use bevy::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    platform::collections::HashMap,
+    prelude::*,
+    text::{PositionedGlyph, TextLayoutInfo},
+};
+
+use crate::{
+    BlankRuby, FuriganaSettings, Ruby, RubyAlign, RubyGapMode, RubyMaxWidth, RubyMode,
+    RubyOffsetUnit, RubyOverhang, RubyPosition, RubyVerticalAlign, SectionRectTieBreak,
+};
+
+/// A glyph in a section of the base text.
+pub(crate) struct SectionGlyph<'a> {
+    /// Bounds of the glyph advance, in the same coordinate space as `TextLayoutInfo::section_rects`.
+    pub rect: Rect,
+    /// Source text of the glyph cluster.
+    pub text: &'a str,
+    /// Y coordinate of the baseline of the glyph.
+    pub baseline: f32,
+}
+
+/// Rects of the text sections of a text block by entity, and its glyphs by line, so that finding
+/// the section of each ruby and the glyphs in it doesn't scan all of `TextLayoutInfo`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SectionRects {
+    rects: EntityHashMap<Vec<Rect>>,
+    /// Lines of the text block sorted from top to bottom, or `None` if their section rects
+    /// overlap, in which case glyphs are looked up by scanning all of them.
+    lines: Option<Vec<GlyphLine>>,
+}
+
+/// Glyphs of a visual line of a text block.
+#[derive(Clone, Debug)]
+struct GlyphLine {
+    /// Vertical extent of the line, as in its section rects.
+    min_y: f32,
+    max_y: f32,
+    /// Pen X and index in `TextLayoutInfo::glyphs` of the glyphs on the line, sorted by pen X.
+    glyphs: Vec<(f32, usize)>,
+}
+
+impl SectionRects {
+    pub fn new(layout_info: &TextLayoutInfo) -> Self {
+        let mut rects = EntityHashMap::<Vec<Rect>>::default();
+        for &(entity, rect) in &layout_info.section_rects {
+            rects.entry(entity).or_default().push(rect);
+        }
+        Self {
+            rects,
+            lines: glyph_lines(layout_info),
+        }
+    }
+
+    /// Rects of the section of `entity`, one per line it wraps to, in layout order.
+    fn get(&self, entity: Entity) -> &[Rect] {
+        self.rects.get(&entity).map_or(&[], Vec::as_slice)
+    }
+
+    /// Pen X and index of the glyphs on the line of `section_rect` sorted by pen X, or `None` if
+    /// it is not a line of the text block (e.g. of a synthetic layout).
+    fn line_glyphs(&self, section_rect: Rect) -> Option<&[(f32, usize)]> {
+        let lines = self.lines.as_ref()?;
+        let index = lines
+            .binary_search_by(|line| line.min_y.total_cmp(&section_rect.min.y))
+            .ok()?;
+        let line = &lines[index];
+        (line.max_y == section_rect.max.y).then_some(line.glyphs.as_slice())
+    }
+}
+
+/// Groups the glyphs of `layout_info` by the line of the section rects they are on.
+fn glyph_lines(layout_info: &TextLayoutInfo) -> Option<Vec<GlyphLine>> {
+    let mut extents = layout_info
+        .section_rects
+        .iter()
+        .map(|(_, rect)| (rect.min.y, rect.max.y))
+        .collect::<Vec<_>>();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    extents.dedup();
+    if extents.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+        return None;
+    }
+
+    let mut lines = extents
+        .into_iter()
+        .map(|(min_y, max_y)| GlyphLine {
+            min_y,
+            max_y,
+            glyphs: vec![],
+        })
+        .collect::<Vec<_>>();
+    for (index, glyph) in layout_info.glyphs.iter().enumerate() {
+        let y = glyph.position.y;
+        let below = lines.partition_point(|line| line.min_y <= y);
+        if let Some(line) = below.checked_sub(1).map(|line| &mut lines[line])
+            && y < line.max_y
+        {
+            line.glyphs.push((pen_x(glyph), index));
+        }
+    }
+    for line in &mut lines {
+        line.glyphs.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    }
+    Some(lines)
+}
+
+/// X coordinate of the pen position of `glyph`, where its advance starts.
+fn pen_x(glyph: &PositionedGlyph) -> f32 {
+    glyph.position.x - glyph.size.x / 2.0 - glyph.atlas_info.location.offset.x as f32
+}
+
+/// [`SectionRects`] of text blocks by their root entity, reindexed only when their layout changes.
+#[derive(Default)]
+pub(crate) struct SectionRectsCache {
+    section_rects: EntityHashMap<SectionRects>,
+    used: EntityHashSet,
+}
+
+impl SectionRectsCache {
+    /// Section rects of the text block of `text_root`, reindexed if its layout `changed` since the
+    /// last lookup.
+    pub fn get(
+        &mut self,
+        text_root: Entity,
+        layout_info: &TextLayoutInfo,
+        changed: bool,
+    ) -> &SectionRects {
+        self.used.insert(text_root);
+        if changed || !self.section_rects.contains_key(&text_root) {
+            self.section_rects
+                .insert(text_root, SectionRects::new(layout_info));
+        }
+        &self.section_rects[&text_root]
+    }
+
+    /// Forgets the text blocks not looked up since the last call, whose layout changes may have
+    /// gone unnoticed (or which are despawned).
+    pub fn retain_used(&mut self) {
+        let used = core::mem::take(&mut self.used);
+        self.section_rects
+            .retain(|text_root, _| used.contains(text_root));
+    }
+}
+
+/// Laid out text block to place ruby on.
+pub(crate) struct TextBlock<'a> {
+    pub layout_info: &'a TextLayoutInfo,
+    section_rects: Cow<'a, SectionRects>,
+    /// Lines of the text block, as returned by [`text_lines`].
+    pub lines: &'a [String],
+}
+
+impl<'a> TextBlock<'a> {
+    /// Indexes the section rects of `layout_info`, for a one-off placement.
+    pub fn new(layout_info: &'a TextLayoutInfo, lines: &'a [String]) -> Self {
+        Self {
+            layout_info,
+            section_rects: Cow::Owned(SectionRects::new(layout_info)),
+            lines,
+        }
+    }
+
+    /// Uses `section_rects` already indexed from `layout_info`.
+    pub fn with_section_rects(
+        layout_info: &'a TextLayoutInfo,
+        section_rects: &'a SectionRects,
+        lines: &'a [String],
+    ) -> Self {
+        Self {
+            layout_info,
+            section_rects: Cow::Borrowed(section_rects),
+            lines,
+        }
+    }
+}
+
+/// Finds the bounds of the text section of `entity` used for placing ruby.
+///
+/// A section wrapping to multiple lines has a rect per line, in which case `tie_break` chooses
+/// the one to annotate. For ruby beside vertical text ([`RubyPosition::is_beside`]), the lines
+/// make up a column, and all of them are annotated.
+pub(crate) fn base_rect(
+    block: &TextBlock,
+    entity: Entity,
+    tie_break: SectionRectTieBreak,
+    position: RubyPosition,
+) -> Option<Rect> {
+    if position.is_beside() {
+        return block
+            .section_rects
+            .get(entity)
+            .iter()
+            .copied()
+            .reduce(|a, b| a.union(b));
+    }
+
+    let section_rect = section_rect(block, entity, tie_break)?;
+    let glyphs = section_glyphs(block, section_rect);
+    Some(trim_trailing_whitespace(section_rect, &glyphs))
+}
+
+/// Places ruby text of `size` for `ruby` on the text section of `entity`, or returns `None` if the
+/// section is not laid out.
+///
+/// Everything is in the same coordinate space as `TextLayoutInfo::section_rects`, including
+/// `cap_height` of the base text font, which is used with [`RubyAnchor::CapHeight`](crate::RubyAnchor::CapHeight)
+/// and [`RubyGapMode::Fixed`], `descender` of the font, below which `Under` ruby is pushed, and
+/// `font_size` of the base text, which [`RubyOffsetUnit::Em`] offsets are relative to.
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn place_ruby(
+    ruby: &Ruby,
+    block: &TextBlock,
+    entity: Entity,
+    settings: &FuriganaSettings,
+    size: Vec2,
+    cap_height: Option<f32>,
+    descender: Option<f32>,
+    font_size: f32,
+) -> Option<RubyBox> {
+    if ruby.is_blank() && settings.blank_ruby == BlankRuby::Skip {
+        return None;
+    }
+
+    let layout_info = block.layout_info;
+    let tie_break = settings.section_rect_tie_break;
+    let fitted = |section_rect: Rect| {
+        let scale = max_width_scale(
+            ruby.max_width,
+            section_rect,
+            size,
+            layout_info.scale_factor,
+            settings.ruby_min_fit_scale,
+        );
+        let mut ruby_box = RubyBox::new(ruby, section_rect, size * scale);
+        ruby_box.scale *= scale;
+        ruby_box
+    };
+    let offset = ruby.offset
+        * match ruby.offset_unit {
+            RubyOffsetUnit::Pixels => layout_info.scale_factor,
+            RubyOffsetUnit::Em => font_size,
+        };
+    let fixed_gap = match ruby.gap {
+        RubyGapMode::Auto => None,
+        RubyGapMode::Fixed(gap) => Some(gap * layout_info.scale_factor),
+    };
+
+    if let Some(base_rect_override) = ruby.base_rect_override {
+        let mut ruby_box = fitted(base_rect_override);
+        if let Some(gap) = fixed_gap {
+            ruby_box.set_gap(base_rect_override.min.y, base_rect_override.max.y, gap);
+        }
+        return finite(ruby_box.offset_from_base(offset));
+    }
+
+    let section_rect = base_rect(block, entity, tie_break, ruby.position)?;
+    let mut ruby_box = fitted(section_rect);
+    let cap_top = cap_height.and_then(|cap_height| cap_top(block, entity, tie_break, cap_height));
+    if let Some(gap) = fixed_gap {
+        ruby_box.set_gap(
+            cap_top.unwrap_or(section_rect.min.y),
+            baseline(block, entity, tie_break).unwrap_or(section_rect.max.y),
+            gap,
+        );
+    } else if let Some(cap_top) = cap_top {
+        ruby_box.anchor_to_cap_top(cap_top);
+    }
+    if let Some(descender_line) =
+        descender.and_then(|descender| Some(baseline(block, entity, tie_break)? + descender))
+    {
+        ruby_box.clear_descender_line(descender_line);
+    }
+    finite(ruby_box.offset_from_base(offset))
+}
+
+/// Returns `ruby_box` unless degenerate text layout (e.g. a zero or negative line height) made it
+/// NaN or infinite, in which case the ruby is not shown.
+fn finite(ruby_box: RubyBox) -> Option<RubyBox> {
+    if ruby_box.center.is_finite() && ruby_box.size.is_finite() && ruby_box.scale.is_finite() {
+        return Some(ruby_box);
+    }
+    warn_once!("Skipping ruby placed at a non-finite position: {ruby_box:?}");
+    None
+}
+
+/// Scale to shrink ruby text of `size` by to fit in `max_width`, but not below `min_scale`.
+fn max_width_scale(
+    max_width: RubyMaxWidth,
+    section_rect: Rect,
+    size: Vec2,
+    scale_factor: f32,
+    min_scale: f32,
+) -> f32 {
+    let limit = match max_width {
+        RubyMaxWidth::None => return 1.0,
+        RubyMaxWidth::MatchBase => section_rect.width(),
+        RubyMaxWidth::Px(px) => px * scale_factor,
+    };
+    if size.x > limit {
+        (limit / size.x).max(min_scale)
+    } else {
+        1.0
+    }
+}
+
+/// Finds the top of the capital letters in the text section of `entity`, given the cap height
+/// of its font in the same coordinate space as `TextLayoutInfo::section_rects`.
+pub(crate) fn cap_top(
+    block: &TextBlock,
+    entity: Entity,
+    tie_break: SectionRectTieBreak,
+    cap_height: f32,
+) -> Option<f32> {
+    Some(baseline(block, entity, tie_break)? - cap_height)
+}
+
+/// Finds the baseline of the text section of `entity`.
+fn baseline(block: &TextBlock, entity: Entity, tie_break: SectionRectTieBreak) -> Option<f32> {
+    let section_rect = section_rect(block, entity, tie_break)?;
+    let glyphs = section_glyphs(block, section_rect);
+    Some(glyphs.first()?.baseline)
+}
+
+/// Rect of the text section of `entity` chosen by `tie_break`.
+fn section_rect(block: &TextBlock, entity: Entity, tie_break: SectionRectTieBreak) -> Option<Rect> {
+    let mut rects = block.section_rects.get(entity).iter().copied();
+    match tie_break {
+        SectionRectTieBreak::Topmost => rects.min_by(|a, b| a.min.y.total_cmp(&b.min.y)),
+        SectionRectTieBreak::First => rects.next(),
+        // `max_by` returns the last of equally wide rects, so reverse to prefer the first
+        SectionRectTieBreak::Widest => rects.rev().max_by(|a, b| a.width().total_cmp(&b.width())),
+    }
+}
+
+/// Splits the text of all spans in a text block into lines, the same way the text layout does.
+pub(crate) fn text_lines<'a>(spans: impl Iterator<Item = &'a str>) -> Vec<String> {
+    spans
+        .collect::<String>()
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+/// Collects the glyphs of `block` laid out within `section_rect`, in visual order.
+pub(crate) fn section_glyphs<'a>(
+    block: &TextBlock<'a>,
+    section_rect: Rect,
+) -> Vec<SectionGlyph<'a>> {
+    let layout_info = block.layout_info;
+    // Glyph positions are snapped to whole pixels, while section rects are not
+    let x_range = section_rect.min.x.round()..section_rect.max.x.round();
+    let glyphs = match block.section_rects.line_glyphs(section_rect) {
+        Some(line) => {
+            let start = line.partition_point(|&(pen_x, _)| pen_x < x_range.start);
+            let end = line.partition_point(|&(pen_x, _)| pen_x < x_range.end);
+            line[start..end.max(start)]
+                .iter()
+                .map(|&(pen_x, index)| (pen_x, &layout_info.glyphs[index]))
+                .collect::<Vec<_>>()
+        }
+        None => {
+            let mut glyphs = layout_info
+                .glyphs
+                .iter()
+                .filter(|glyph| {
+                    (section_rect.min.y..section_rect.max.y).contains(&glyph.position.y)
+                })
+                .map(|glyph| (pen_x(glyph), glyph))
+                .filter(|(pen_x, _)| x_range.contains(pen_x))
+                .collect::<Vec<_>>();
+            glyphs.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            glyphs
+        }
+    };
+
+    let ends = glyphs
+        .iter()
+        .skip(1)
+        .map(|&(pen_x, _)| pen_x)
+        .chain([section_rect.max.x]);
+    glyphs
+        .iter()
+        .zip(ends)
+        .map(|(&(start, glyph), end)| SectionGlyph {
+            rect: Rect::new(start, section_rect.min.y, end, section_rect.max.y),
+            text: block
+                .lines
+                .get(glyph.line_index)
+                .and_then(|line| line.get(glyph.byte_index..glyph.byte_index + glyph.byte_length))
+                .unwrap_or_default(),
+            baseline: glyph.position.y - glyph.size.y / 2.0
+                + glyph.atlas_info.location.offset.y as f32,
+        })
+        .collect()
+}
+
+/// Shrinks `section_rect` so that it excludes trailing whitespace glyphs.
+pub(crate) fn trim_trailing_whitespace(section_rect: Rect, glyphs: &[SectionGlyph]) -> Rect {
+    let Some(last_visible) = glyphs
+        .iter()
+        .rfind(|glyph| !glyph.text.chars().all(char::is_whitespace))
+    else {
+        return section_rect;
+    };
+
+    Rect {
+        max: Vec2::new(last_visible.rect.max.x, section_rect.max.y),
+        ..section_rect
+    }
+}
+
+/// Axis ruby at `position` is aligned along.
+fn along(position: RubyPosition) -> Vec2 {
+    if position.is_beside() {
+        Vec2::Y
+    } else {
+        Vec2::X
+    }
+}
+
+/// Direction away from the base text for ruby at `position`.
+fn outward(position: RubyPosition) -> Vec2 {
+    match position {
+        RubyPosition::Over => Vec2::NEG_Y,
+        RubyPosition::Under => Vec2::Y,
+        RubyPosition::Right => Vec2::X,
+        RubyPosition::Left => Vec2::NEG_X,
+    }
+}
+
+/// Ruby text box placed against its base text, in the text layout's coordinate space (Y+ down).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RubyBox {
+    pub center: Vec2,
+    /// Laid out size, after `scale` is applied.
+    pub size: Vec2,
+    /// Scale to apply to the ruby text, to fit it in its [`Ruby::max_width`].
+    pub scale: f32,
+    pub position: RubyPosition,
+    /// Bounds of the base text the ruby is placed against.
+    pub base_rect: Rect,
+}
+
+impl RubyBox {
+    /// Places a ruby text of `size` against `section_rect` of the base text.
+    ///
+    /// Ruby is aligned along the X axis over or under horizontal text, and along the Y axis beside
+    /// vertical text.
+    pub fn new(ruby: &Ruby, section_rect: Rect, size: Vec2) -> Self {
+        let along = along(ruby.position);
+        let mono = matches!(ruby.mode, RubyMode::Mono(_)) && !ruby.position.is_beside();
+        let mut size = size;
+        if (ruby.align.spreads_glyphs() || mono) && !ruby.position.is_beside() {
+            // Glyphs are spread across the base by `justify_glyphs`, or over each character of it
+            // by `center_mono_readings`
+            size.x = size.x.max(section_rect.width());
+        }
+        let align = if mono { RubyAlign::Center } else { ruby.align };
+
+        let (min, max, length) = (
+            section_rect.min.dot(along),
+            section_rect.max.dot(along),
+            size.dot(along),
+        );
+        let aligned = match align {
+            RubyAlign::Start => min + length / 2.0,
+            RubyAlign::Center | RubyAlign::Justify | RubyAlign::Distribute => {
+                f32::midpoint(min, max)
+            }
+            RubyAlign::End => max - length / 2.0,
+        };
+        let mut center = match ruby.position {
+            RubyPosition::Over => Vec2::new(aligned, section_rect.min.y),
+            RubyPosition::Under => Vec2::new(aligned, section_rect.max.y),
+            RubyPosition::Right => Vec2::new(section_rect.max.x, aligned),
+            RubyPosition::Left => Vec2::new(section_rect.min.x, aligned),
+        };
+
+        let outward = outward(ruby.position);
+        let depth = size.dot(outward.abs());
+        center += outward
+            * match ruby.vertical_align {
+                RubyVerticalAlign::Center => 0.0,
+                RubyVerticalAlign::Outside => depth / 2.0,
+                RubyVerticalAlign::Inside => -depth / 2.0,
+            };
+
+        Self {
+            center,
+            size,
+            scale: 1.0,
+            position: ruby.position,
+            base_rect: section_rect,
+        }
+    }
+
+    /// Moves the ruby by `offset`, X along the base text and Y away from it (see [`Ruby::offset`]).
+    pub fn offset_from_base(self, offset: Vec2) -> Self {
+        self.nudged(along(self.position) * offset.x + outward(self.position) * offset.y)
+    }
+
+    /// Moves `Over` ruby to sit right above `cap_top` (see [`cap_top`]) instead of the line box.
+    pub fn anchor_to_cap_top(&mut self, cap_top: f32) {
+        if self.position == RubyPosition::Over {
+            self.center.y = cap_top - self.size.y / 2.0;
+        }
+    }
+
+    /// Moves `Under` ruby down so that it doesn't overlap the base above `descender_line`.
+    pub fn clear_descender_line(&mut self, descender_line: f32) {
+        let top = self.center.y - self.size.y / 2.0;
+        if self.position == RubyPosition::Under && top < descender_line {
+            self.center.y += descender_line - top;
+        }
+    }
+
+    /// Moves the ruby `gap` away from `top` of the base for `Over` ruby, or from `bottom` for
+    /// `Under` ruby. Ruby beside vertical text is moved away from the sides of the base rect.
+    pub fn set_gap(&mut self, top: f32, bottom: f32, gap: f32) {
+        match self.position {
+            RubyPosition::Over => self.center.y = top - gap - self.size.y / 2.0,
+            RubyPosition::Under => self.center.y = bottom + gap + self.size.y / 2.0,
+            RubyPosition::Right => self.center.x = self.base_rect.max.x + gap + self.size.x / 2.0,
+            RubyPosition::Left => self.center.x = self.base_rect.min.x - gap - self.size.x / 2.0,
+        }
+    }
+
+    /// Moves the ruby by `offset` (see [`crate::FuriganaSettings::subpixel_nudge`]).
+    pub fn nudged(self, offset: Vec2) -> Self {
+        Self {
+            center: self.center + offset,
+            ..self
+        }
+    }
+
+    /// Converts from physical to logical pixels.
+    pub fn to_logical(self, scale_factor: f32) -> Self {
+        Self {
+            center: self.center / scale_factor,
+            size: self.size / scale_factor,
+            base_rect: Rect::from_corners(
+                self.base_rect.min / scale_factor,
+                self.base_rect.max / scale_factor,
+            ),
+            ..self
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_center_size(self.center, self.size)
+    }
+
+    /// How far the ruby extends beyond the base on the left and right. Always zero for ruby beside
+    /// vertical text.
+    pub fn overhang(&self) -> RubyOverhang {
+        if self.position.is_beside() {
+            return RubyOverhang::default();
+        }
+        let rect = self.rect();
+        RubyOverhang {
+            left: (self.base_rect.min.x - rect.min.x).max(0.0),
+            right: (rect.max.x - self.base_rect.max.x).max(0.0),
+        }
+    }
+}
+
+/// Spreads the glyphs of a ruby text across `width` (centered on its laid out size), with even
+/// spacing between them, for [`RubyAlign::Justify`] and [`RubyAlign::Distribute`]. Returns whether
+/// any glyph was moved.
+pub(crate) fn justify_glyphs(
+    layout_info: &mut TextLayoutInfo,
+    width: f32,
+    align: RubyAlign,
+) -> bool {
+    let glyph_count = layout_info.glyphs.len();
+    if !align.spreads_glyphs() || glyph_count < 2 || width <= layout_info.size.x {
+        return false;
+    }
+
+    let ink_width = layout_info
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.size.x)
+        .sum::<f32>();
+    let distribute = align == RubyAlign::Distribute;
+    // Distributed glyphs get half a space at each edge, i.e. one more space in total
+    let spaces = if distribute {
+        glyph_count
+    } else {
+        glyph_count - 1
+    };
+    let spacing = (width - ink_width) / spaces as f32;
+    let edge_spacing = if distribute { spacing / 2.0 } else { 0.0 };
+
+    let mut changed = false;
+    let mut left = (layout_info.size.x - width) / 2.0 + edge_spacing;
+    for glyph in &mut layout_info.glyphs {
+        let x = left + glyph.size.x / 2.0;
+        if glyph.position.x != x {
+            glyph.position.x = x;
+            changed = true;
+        }
+        left += glyph.size.x + spacing;
+    }
+    changed
+}
+
+/// Centers of the characters of the text section of `entity` chosen by `tie_break`, to center the
+/// `count` readings of [`RubyMode::Mono`] ruby over, or `None` unless the section has `count`
+/// characters on that line. A glyph cluster of several characters (e.g. a ligature) is split
+/// evenly between them.
+pub(crate) fn mono_base_centers(
+    block: &TextBlock,
+    entity: Entity,
+    tie_break: SectionRectTieBreak,
+    count: usize,
+) -> Option<Vec<f32>> {
+    let section_rect = section_rect(block, entity, tie_break)?;
+    let centers = section_glyphs(block, section_rect)
+        .iter()
+        .flat_map(|glyph| {
+            let chars = glyph.text.chars().count().max(1);
+            let width = glyph.rect.width() / chars as f32;
+            (0..chars).map(move |index| glyph.rect.min.x + width * (index as f32 + 0.5))
+        })
+        .collect::<Vec<_>>();
+    (centers.len() == count).then_some(centers)
+}
+
+/// Moves the glyphs of each of the `readings` making up a [`RubyMode::Mono`] ruby text to center
+/// it at the X coordinate of its character in `centers`, without overlapping the reading before
+/// it. Returns whether any glyph was moved.
+pub(crate) fn center_mono_readings(
+    layout_info: &mut TextLayoutInfo,
+    readings: &[impl AsRef<str>],
+    centers: impl IntoIterator<Item = f32>,
+) -> bool {
+    // Less than this is left as is, so that rounding errors don't move the glyphs every time
+    const EPSILON: f32 = 1e-3;
+
+    let mut changed = false;
+    let mut start = 0;
+    let mut min_left = f32::NEG_INFINITY;
+    for (reading, center) in readings.iter().zip(centers) {
+        let bytes = start..start + reading.as_ref().len();
+        start = bytes.end;
+        let mut glyphs = layout_info
+            .glyphs
+            .iter_mut()
+            .filter(|glyph| bytes.contains(&glyph.byte_index))
+            .collect::<Vec<_>>();
+        let Some((left, right)) = glyphs
+            .iter()
+            .map(|glyph| {
+                let half_width = glyph.size.x / 2.0;
+                (glyph.position.x - half_width, glyph.position.x + half_width)
+            })
+            .reduce(|(a_left, a_right), (b_left, b_right)| {
+                (a_left.min(b_left), a_right.max(b_right))
+            })
+        else {
+            continue;
+        };
+
+        let width = right - left;
+        let centered_left = (center - width / 2.0).max(min_left);
+        min_left = centered_left + width;
+        let shift = centered_left - left;
+        if shift.abs() < EPSILON {
+            continue;
+        }
+        for glyph in &mut glyphs {
+            glyph.position.x += shift;
+        }
+        changed = true;
+    }
+    changed
+}
+
+/// Nudges apart `Under` ruby of a line and `Over` ruby of the next line where they overlap.
+pub(crate) fn separate_adjacent_lines(boxes: &mut [RubyBox]) {
+    let indices_at = |position| {
+        (0..boxes.len())
+            .filter(|&index| boxes[index].position == position)
+            .collect::<Vec<_>>()
+    };
+    let over_indices = indices_at(RubyPosition::Over);
+    for under_index in indices_at(RubyPosition::Under) {
+        for &over_index in &over_indices {
+            let (under, over) = (boxes[under_index], boxes[over_index]);
+            // `Over` ruby of the same line
+            if over.center.y < under.center.y {
+                continue;
+            }
+
+            let overlap = under.rect().intersect(over.rect());
+            if overlap.is_empty() {
+                continue;
+            }
+
+            let nudge = overlap.height() / 2.0;
+            boxes[under_index].center.y -= nudge;
+            boxes[over_index].center.y += nudge;
+        }
+    }
+}
+
+/// Stacks ruby boxes on the same side of the same base text (e.g. a reading and a translation both
+/// `Over`) away from it in order, so that later ones don't cover earlier ones.
+pub(crate) fn stack_same_side(boxes: &mut [RubyBox]) {
+    // Only boxes of the same base are compared, so that this stays linear in long text
+    let mut by_base = HashMap::<[u32; 4], Vec<usize>>::default();
+    for (index, ruby_box) in boxes.iter().enumerate() {
+        let Rect { min, max } = ruby_box.base_rect;
+        by_base
+            .entry([min.x, min.y, max.x, max.y].map(f32::to_bits))
+            .or_default()
+            .push(index);
+    }
+
+    for indices in by_base.values() {
+        for (later_index, &later) in indices.iter().enumerate() {
+            for &earlier in &indices[..later_index] {
+                let (inner, outer) = (boxes[earlier], boxes[later]);
+                if inner.position != outer.position {
+                    continue;
+                }
+
+                if inner.rect().intersect(outer.rect()).is_empty() {
+                    continue;
+                }
+
+                // Move the near edge of the outer box to the far edge of the inner one
+                let outward = outward(outer.position);
+                let far_edge = |ruby_box: RubyBox, sign: f32| {
+                    ruby_box.center.dot(outward) + sign * (ruby_box.size / 2.0).dot(outward.abs())
+                };
+                boxes[later] =
+                    outer.nudged(outward * (far_edge(inner, 1.0) - far_edge(outer, -1.0)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::text::{GlyphAtlasInfo, GlyphAtlasLocation};
+
+    use super::*;
+    use crate::RubyAnchor;
+
+    /// Lays out glyph clusters with the given advances in a single line.
+    fn single_line_layout(clusters: &[(&str, f32)], height: f32) -> TextLayoutInfo {
+        let mut pen_x = 0.0;
+        let mut byte_index = 0;
+        let glyphs = clusters
+            .iter()
+            .map(|&(text, advance)| {
+                let size = if text.chars().all(char::is_whitespace) {
+                    Vec2::ZERO
+                } else {
+                    Vec2::new(advance, height)
+                };
+                let glyph = PositionedGlyph {
+                    position: Vec2::new(pen_x + size.x / 2.0, height / 2.0),
+                    size,
+                    atlas_info: GlyphAtlasInfo {
+                        texture: AssetId::default(),
+                        texture_atlas: AssetId::default(),
+                        location: GlyphAtlasLocation {
+                            glyph_index: 0,
+                            offset: IVec2::ZERO,
+                        },
+                    },
+                    span_index: 0,
+                    line_index: 0,
+                    byte_index,
+                    byte_length: text.len(),
+                };
+                pen_x += advance;
+                byte_index += text.len();
+                glyph
+            })
+            .collect();
+        TextLayoutInfo {
+            scale_factor: 1.0,
+            glyphs,
+            section_rects: vec![(Entity::PLACEHOLDER, Rect::new(0.0, 0.0, pen_x, height))],
+            size: Vec2::new(pen_x, height),
+        }
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        let clusters = [("漢", 10.0), ("字", 10.0), (" ", 10.0), (" ", 10.0)];
+        let layout_info = single_line_layout(&clusters, 20.0);
+        let section_rect = layout_info.section_rects[0].1;
+        let lines = text_lines(clusters.iter().map(|&(text, _)| text));
+
+        let glyphs = section_glyphs(&TextBlock::new(&layout_info, &lines), section_rect);
+        assert_eq!(
+            glyphs.iter().map(|g| g.text).collect::<Vec<_>>(),
+            ["漢", "字", " ", " "]
+        );
+
+        let trimmed = trim_trailing_whitespace(section_rect, &glyphs);
+        assert_eq!(trimmed, Rect::new(0.0, 0.0, 20.0, 20.0));
+        assert_eq!(trimmed.center().x, 10.0);
+    }
+
+    #[test]
+    fn test_section_glyphs_with_emoji() {
+        // Emoji have their own advances, and may consist of multiple characters
+        let clusters = [("笑", 10.0), ("👍🏽", 24.0), ("😀", 24.0), (" ", 10.0)];
+        let mut layout_info = single_line_layout(&clusters, 20.0);
+        // Color emoji bitmaps may overflow their advance
+        let emoji = &mut layout_info.glyphs[2];
+        emoji.size.x = 28.0;
+        emoji.atlas_info.location.offset.x = -2;
+        emoji.position.x = 34.0 - 2.0 + 28.0 / 2.0;
+
+        let section_rect = layout_info.section_rects[0].1;
+        let lines = text_lines(clusters.iter().map(|&(text, _)| text));
+
+        let glyphs = section_glyphs(&TextBlock::new(&layout_info, &lines), section_rect);
+        assert_eq!(
+            glyphs
+                .iter()
+                .map(|g| (g.text, g.rect.min.x, g.rect.max.x))
+                .collect::<Vec<_>>(),
+            [
+                ("笑", 0.0, 10.0),
+                ("👍🏽", 10.0, 34.0),
+                ("😀", 34.0, 58.0),
+                (" ", 58.0, 68.0)
+            ]
+        );
+
+        let trimmed = trim_trailing_whitespace(section_rect, &glyphs);
+        assert_eq!(trimmed.center().x, 29.0);
+    }
+
+    #[test]
+    fn test_section_glyphs_with_astral_plane_characters() {
+        let clusters = [("𠮷", 10.0), ("野", 10.0), ("𩸽", 10.0), (" ", 10.0)];
+        let layout_info = single_line_layout(&clusters, 20.0);
+        let section_rect = layout_info.section_rects[0].1;
+        let lines = text_lines(clusters.iter().map(|&(text, _)| text));
+
+        let glyphs = section_glyphs(&TextBlock::new(&layout_info, &lines), section_rect);
+        assert_eq!(
+            glyphs.iter().map(|g| g.text).collect::<Vec<_>>(),
+            ["𠮷", "野", "𩸽", " "]
+        );
+        assert_eq!(
+            trim_trailing_whitespace(section_rect, &glyphs),
+            Rect::new(0.0, 0.0, 30.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn test_section_glyphs_on_wrapped_lines() {
+        let clusters = [("漢", 10.0), ("字", 10.0), ("仮", 10.0), ("名", 10.0)];
+        let mut layout_info = single_line_layout(&clusters, 20.0);
+        // "仮名" wraps after "仮", so "名" is at the start of the second line
+        let wrapped = &mut layout_info.glyphs[3];
+        wrapped.position += Vec2::new(-30.0, 20.0);
+        layout_info.section_rects = vec![
+            (Entity::PLACEHOLDER, Rect::new(0.0, 0.0, 20.0, 20.0)),
+            (Entity::PLACEHOLDER, Rect::new(20.0, 0.0, 30.0, 20.0)),
+            (Entity::PLACEHOLDER, Rect::new(0.0, 20.0, 10.0, 40.0)),
+        ];
+        let lines = text_lines(clusters.iter().map(|&(text, _)| text));
+        let block = TextBlock::new(&layout_info, &lines);
+
+        let texts = |section_rect| {
+            section_glyphs(&block, section_rect)
+                .iter()
+                .map(|glyph| glyph.text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(Rect::new(0.0, 0.0, 20.0, 20.0)), ["漢", "字"]);
+        assert_eq!(texts(Rect::new(20.0, 0.0, 30.0, 20.0)), ["仮"]);
+        assert_eq!(texts(Rect::new(0.0, 20.0, 10.0, 40.0)), ["名"]);
+        // Rects not of a line of the block are looked up by scanning all glyphs
+        assert_eq!(
+            texts(Rect::new(0.0, 0.0, 40.0, 40.0)),
+            ["漢", "名", "字", "仮"]
+        );
+    }
+
+    #[test]
+    fn test_separate_adjacent_lines() {
+        let ruby_size = Vec2::new(20.0, 10.0);
+        let mut boxes = [
+            // Line 1
+            RubyBox::new(
+                &Ruby {
+                    position: RubyPosition::Under,
+                    ..default()
+                },
+                Rect::new(0.0, 0.0, 30.0, 30.0),
+                ruby_size,
+            ),
+            // Line 2
+            RubyBox::new(
+                &Ruby::default(),
+                Rect::new(10.0, 30.0, 40.0, 60.0),
+                ruby_size,
+            ),
+            // Line 2, not overlapping horizontally
+            RubyBox::new(
+                &Ruby::default(),
+                Rect::new(100.0, 30.0, 130.0, 60.0),
+                ruby_size,
+            ),
+        ];
+
+        separate_adjacent_lines(&mut boxes);
+
+        assert_eq!(boxes[0].center, Vec2::new(15.0, 25.0));
+        assert_eq!(boxes[1].center, Vec2::new(25.0, 35.0));
+        assert_eq!(boxes[2].center, Vec2::new(115.0, 30.0));
+        assert!(boxes[0].rect().intersect(boxes[1].rect()).is_empty());
+    }
+
+    #[test]
+    fn test_stack_same_side() {
+        let base_rect = Rect::new(0.0, 30.0, 30.0, 60.0);
+        let under = Ruby {
+            position: RubyPosition::Under,
+            ..default()
+        };
+        let mut boxes = [
+            RubyBox::new(&Ruby::default(), base_rect, Vec2::new(20.0, 10.0)),
+            RubyBox::new(&under, base_rect, Vec2::new(20.0, 10.0)),
+            RubyBox::new(&Ruby::default(), base_rect, Vec2::new(40.0, 6.0)),
+            // Another base
+            RubyBox::new(
+                &Ruby::default(),
+                Rect::new(30.0, 30.0, 60.0, 60.0),
+                Vec2::new(20.0, 10.0),
+            ),
+        ];
+
+        stack_same_side(&mut boxes);
+
+        assert_eq!(boxes[0].center, Vec2::new(15.0, 30.0));
+        assert_eq!(boxes[1].center, Vec2::new(15.0, 60.0));
+        // Right outside the first one
+        assert_eq!(boxes[2].center, Vec2::new(15.0, 22.0));
+        assert_eq!(boxes[3].center, Vec2::new(45.0, 30.0));
+    }
+
+    #[test]
+    fn test_ruby_overhang() {
+        let section_rect = Rect::new(10.0, 0.0, 30.0, 20.0);
+        let overhang = |align| {
+            let ruby = Ruby { align, ..default() };
+            RubyBox::new(&ruby, section_rect, Vec2::new(40.0, 10.0)).overhang()
+        };
+
+        assert_eq!(
+            overhang(RubyAlign::Center),
+            RubyOverhang {
+                left: 10.0,
+                right: 10.0
+            }
+        );
+        assert_eq!(
+            overhang(RubyAlign::Start),
+            RubyOverhang {
+                left: 0.0,
+                right: 20.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_mono_ruby_box_spans_base() {
+        let section_rect = Rect::new(10.0, 0.0, 30.0, 20.0);
+        let mono = Ruby {
+            align: RubyAlign::Start,
+            ..Ruby::mono(["か", "な"])
+        };
+
+        // Readings are centered over their own characters, whatever the alignment
+        let narrow = RubyBox::new(&mono, section_rect, Vec2::new(10.0, 10.0));
+        assert_eq!(narrow.scale, 1.0);
+        assert_eq!(narrow.rect(), Rect::new(10.0, -5.0, 30.0, 5.0));
+
+        // Not shrunk to fit the base
+        let wide = RubyBox::new(&mono, section_rect, Vec2::new(30.0, 10.0));
+        assert_eq!(wide.scale, 1.0);
+        assert_eq!(wide.rect(), Rect::new(5.0, -5.0, 35.0, 5.0));
+    }
+
+    #[test]
+    fn test_center_mono_readings() {
+        let clusters = [("東", 40.0), ("京", 40.0)];
+        let layout_info = single_line_layout(&clusters, 40.0);
+        let lines = text_lines(clusters.iter().map(|&(text, _)| text));
+        let block = TextBlock::new(&layout_info, &lines);
+        let tie_break = SectionRectTieBreak::First;
+        assert_eq!(
+            mono_base_centers(&block, Entity::PLACEHOLDER, tie_break, 3),
+            None
+        );
+        let centers = mono_base_centers(&block, Entity::PLACEHOLDER, tie_break, 2).unwrap();
+        assert_eq!(centers, [20.0, 60.0]);
+
+        // "とう" + "きょう" of 10px each
+        let readings = ["とう", "きょう"];
+        let mut ruby_layout_info = single_line_layout(
+            &["と", "う", "き", "ょ", "う"].map(|text| (text, 10.0)),
+            10.0,
+        );
+        assert!(center_mono_readings(
+            &mut ruby_layout_info,
+            &readings,
+            centers.clone()
+        ));
+        let glyph_centers = |layout_info: &TextLayoutInfo| {
+            layout_info
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.position.x)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            glyph_centers(&ruby_layout_info),
+            [15.0, 25.0, 50.0, 60.0, 70.0]
+        );
+        // Already centered
+        assert!(!center_mono_readings(
+            &mut ruby_layout_info,
+            &readings,
+            centers
+        ));
+
+        // A reading wider than its character is pushed along so as not to overlap the one before
+        assert!(center_mono_readings(
+            &mut ruby_layout_info,
+            &readings,
+            [20.0, 30.0]
+        ));
+        assert_eq!(
+            glyph_centers(&ruby_layout_info),
+            [15.0, 25.0, 35.0, 45.0, 55.0]
+        );
+    }
+
+    #[test]
+    fn test_justify_glyphs() {
+        // "かんじ" of 3 x 10px over a 60px wide base
+        let mut layout_info = single_line_layout(&[("か", 10.0), ("ん", 10.0), ("じ", 10.0)], 10.0);
+        assert!(justify_glyphs(&mut layout_info, 60.0, RubyAlign::Justify));
+        let centers = |layout_info: &TextLayoutInfo| {
+            layout_info
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.position.x)
+                .collect::<Vec<_>>()
+        };
+        // Relative to the 30px wide ruby text, flush with the base at -15px and 45px
+        assert_eq!(centers(&layout_info), [-10.0, 15.0, 40.0]);
+        // Applying again is a no-op
+        assert!(!justify_glyphs(&mut layout_info, 60.0, RubyAlign::Justify));
+
+        let ruby = Ruby {
+            align: RubyAlign::Justify,
+            ..Ruby::new("かんじ")
+        };
+        let ruby_box = RubyBox::new(
+            &ruby,
+            Rect::new(100.0, 0.0, 160.0, 20.0),
+            Vec2::new(30.0, 10.0),
+        );
+        assert_eq!(ruby_box.rect().min.x, 100.0);
+        assert_eq!(ruby_box.rect().max.x, 160.0);
+
+        // A single character is left centered
+        let mut layout_info = single_line_layout(&[("じ", 10.0)], 10.0);
+        assert!(!justify_glyphs(&mut layout_info, 60.0, RubyAlign::Justify));
+        assert_eq!(centers(&layout_info), [5.0]);
+
+        // Not spread with other alignments
+        let mut layout_info = single_line_layout(&[("か", 10.0), ("ん", 10.0)], 10.0);
+        assert!(!justify_glyphs(&mut layout_info, 60.0, RubyAlign::Center));
+        assert_eq!(centers(&layout_info), [5.0, 15.0]);
+    }
+
+    #[test]
+    fn test_distribute_glyphs() {
+        let centers = |layout_info: &TextLayoutInfo| {
+            layout_info
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.position.x)
+                .collect::<Vec<_>>()
+        };
+
+        // "かん" of 2 x 10px over a 60px wide base, at 1/4 and 3/4 of the base spanning -20px to
+        // 40px relative to the 20px wide ruby text
+        let mut layout_info = single_line_layout(&[("か", 10.0), ("ん", 10.0)], 10.0);
+        assert!(justify_glyphs(
+            &mut layout_info,
+            60.0,
+            RubyAlign::Distribute
+        ));
+        assert_eq!(centers(&layout_info), [-5.0, 25.0]);
+
+        // "かんじ" with 1:2:1 spacing of 5px, 10px, 10px and 5px
+        let mut layout_info = single_line_layout(&[("か", 10.0), ("ん", 10.0), ("じ", 10.0)], 10.0);
+        assert!(justify_glyphs(
+            &mut layout_info,
+            60.0,
+            RubyAlign::Distribute
+        ));
+        assert_eq!(centers(&layout_info), [-5.0, 15.0, 35.0]);
+
+        // A single character is left centered
+        let mut layout_info = single_line_layout(&[("じ", 10.0)], 10.0);
+        assert!(!justify_glyphs(
+            &mut layout_info,
+            60.0,
+            RubyAlign::Distribute
+        ));
+        assert_eq!(centers(&layout_info), [5.0]);
+
+        let ruby = Ruby {
+            align: RubyAlign::Distribute,
+            ..Ruby::new("かん")
+        };
+        let ruby_box = RubyBox::new(
+            &ruby,
+            Rect::new(100.0, 0.0, 160.0, 20.0),
+            Vec2::new(20.0, 10.0),
+        );
+        assert_eq!(ruby_box.rect().min.x, 100.0);
+        assert_eq!(ruby_box.rect().max.x, 160.0);
+    }
+
+    #[test]
+    fn test_base_rect_of_wrapped_section() {
+        // "漢字仮名" wrapped after "漢字"
+        let mut layout_info = single_line_layout(&[("漢", 10.0), ("字", 10.0)], 20.0);
+        let second_line = single_line_layout(&[("仮", 10.0), ("名", 10.0)], 20.0);
+        layout_info
+            .glyphs
+            .extend(second_line.glyphs.into_iter().map(|mut glyph| {
+                glyph.position.y += 20.0;
+                glyph.byte_index += "漢字".len();
+                glyph
+            }));
+        layout_info.section_rects = vec![
+            (Entity::PLACEHOLDER, Rect::new(0.0, 20.0, 20.0, 40.0)),
+            (Entity::PLACEHOLDER, Rect::new(0.0, 0.0, 20.0, 20.0)),
+        ];
+        layout_info.size = Vec2::new(20.0, 40.0);
+        let lines = text_lines(["漢字仮名"].into_iter());
+
+        let block = TextBlock::new(&layout_info, &lines);
+        let base_rect =
+            |tie_break| base_rect(&block, Entity::PLACEHOLDER, tie_break, RubyPosition::Over);
+        assert_eq!(
+            base_rect(SectionRectTieBreak::Topmost),
+            Some(Rect::new(0.0, 0.0, 20.0, 20.0))
+        );
+        assert_eq!(
+            base_rect(SectionRectTieBreak::First),
+            Some(Rect::new(0.0, 20.0, 20.0, 40.0))
+        );
+    }
+
+    #[test]
+    fn test_base_rect_override() {
+        let layout_info = single_line_layout(&[("漢", 10.0), ("字", 10.0)], 20.0);
+        let ruby_center = |base_rect_override| {
+            let ruby = Ruby {
+                base_rect_override,
+                ..Ruby::new("かんじ")
+            };
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(30.0, 10.0),
+                None,
+                None,
+                20.0,
+            )
+            .unwrap()
+            .center
+        };
+
+        assert_eq!(ruby_center(None), Vec2::new(10.0, 0.0));
+        // Placed over the given rect instead of the section rect
+        assert_eq!(
+            ruby_center(Some(Rect::new(100.0, 50.0, 140.0, 70.0))),
+            Vec2::new(120.0, 50.0)
+        );
+    }
+
+    #[test]
+    fn test_ruby_max_width() {
+        let layout_info = single_line_layout(&[], 20.0);
+        let ruby_rect = |max_width| {
+            let ruby = Ruby {
+                max_width,
+                base_rect_override: Some(Rect::new(0.0, 0.0, 40.0, 20.0)),
+                ..Ruby::new("レールガン")
+            };
+            let ruby_box = place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(60.0, 10.0),
+                None,
+                None,
+                20.0,
+            )
+            .unwrap();
+            (ruby_box.rect().width(), ruby_box.scale)
+        };
+
+        assert_eq!(ruby_rect(RubyMaxWidth::None), (60.0, 1.0));
+        assert_eq!(ruby_rect(RubyMaxWidth::MatchBase).0, 40.0);
+        assert_eq!(ruby_rect(RubyMaxWidth::Px(90.0)), (60.0, 1.0));
+        // Not shrunk below `ruby_min_fit_scale`
+        assert_eq!(ruby_rect(RubyMaxWidth::Px(20.0)), (30.0, 0.5));
+    }
+
+    #[test]
+    fn test_ruby_offset() {
+        let mut layout_info = single_line_layout(&[], 20.0);
+        layout_info.scale_factor = 2.0;
+        let ruby_center = |position| {
+            let ruby = Ruby {
+                position,
+                offset: Vec2::new(1.0, 3.0),
+                base_rect_override: Some(Rect::new(0.0, 0.0, 40.0, 20.0)),
+                ..Ruby::new("かんじ")
+            };
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(30.0, 10.0),
+                None,
+                None,
+                20.0,
+            )
+            .unwrap()
+            .center
+        };
+
+        // Away from the base in physical pixels, whichever side the ruby is on
+        assert_eq!(ruby_center(RubyPosition::Over), Vec2::new(22.0, -6.0));
+        assert_eq!(ruby_center(RubyPosition::Under), Vec2::new(22.0, 26.0));
+    }
+
+    #[test]
+    fn test_em_ruby_offset() {
+        let layout_info = single_line_layout(&[], 20.0);
+        let ruby = Ruby {
+            offset: Vec2::new(0.0, 0.25),
+            offset_unit: RubyOffsetUnit::Em,
+            base_rect_override: Some(Rect::new(0.0, 0.0, 40.0, 20.0)),
+            ..Ruby::new("かんじ")
+        };
+        let ruby_center = |font_size| {
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(30.0, 10.0),
+                None,
+                None,
+                font_size,
+            )
+            .unwrap()
+            .center
+        };
+
+        assert_eq!(ruby_center(20.0), Vec2::new(20.0, -5.0));
+        assert_eq!(ruby_center(40.0), Vec2::new(20.0, -10.0));
+    }
+
+    #[test]
+    fn test_ruby_on_degenerate_base() {
+        let layout_info = single_line_layout(&[], 0.0);
+        let place = |ruby: Ruby| {
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(10.0, 30.0),
+                None,
+                None,
+                20.0,
+            )
+        };
+
+        // Beside vertical text with a zero line height, mono ruby is placed as a group
+        let ruby_box = place(Ruby {
+            position: RubyPosition::Right,
+            base_rect_override: Some(Rect::new(0.0, 0.0, 20.0, 0.0)),
+            ..Ruby::mono(["かん", "じ"])
+        })
+        .unwrap();
+        assert_eq!(ruby_box.scale, 1.0);
+        assert_eq!(ruby_box.center, Vec2::new(20.0, 0.0));
+
+        let nan_rect = Rect {
+            min: Vec2::ZERO,
+            max: Vec2::new(20.0, f32::NAN),
+        };
+        assert_eq!(
+            place(Ruby {
+                base_rect_override: Some(nan_rect),
+                ..Ruby::new("かんじ")
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_blank_ruby() {
+        let layout_info = single_line_layout(&[], 20.0);
+        let ruby = Ruby {
+            align: RubyAlign::Start,
+            base_rect_override: Some(Rect::new(0.0, 0.0, 40.0, 20.0)),
+            ..Ruby::new("  ")
+        };
+        let place = |blank_ruby, size| {
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings {
+                    blank_ruby,
+                    ..default()
+                },
+                size,
+                None,
+                None,
+                20.0,
+            )
+        };
+
+        assert!(place(BlankRuby::Skip, Vec2::new(8.0, 10.0)).is_none());
+        // A thin space at the start of the base
+        let rect = place(BlankRuby::ThinSpace, Vec2::new(4.0, 10.0))
+            .unwrap()
+            .rect();
+        assert_eq!((rect.min.x, rect.width()), (0.0, 4.0));
+    }
+
+    #[test]
+    fn test_ruby_beside_vertical_text() {
+        // "漢字" written one character per line
+        let mut layout_info = single_line_layout(&[("漢", 20.0)], 20.0);
+        layout_info.section_rects = vec![
+            (Entity::PLACEHOLDER, Rect::new(0.0, 0.0, 20.0, 20.0)),
+            (Entity::PLACEHOLDER, Rect::new(0.0, 20.0, 20.0, 40.0)),
+        ];
+        let lines = text_lines(["漢\n字"].into_iter());
+        let ruby_rect = |position, align| {
+            let ruby = Ruby {
+                position,
+                align,
+                ..Ruby::new("かんじ")
+            };
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &lines),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(10.0, 30.0),
+                None,
+                None,
+                20.0,
+            )
+            .unwrap()
+            .rect()
+        };
+
+        // Centered on the side of the whole column
+        assert_eq!(
+            ruby_rect(RubyPosition::Right, RubyAlign::Center),
+            Rect::new(15.0, 5.0, 25.0, 35.0)
+        );
+        assert_eq!(
+            ruby_rect(RubyPosition::Left, RubyAlign::Start),
+            Rect::new(-5.0, 0.0, 5.0, 30.0)
+        );
+        assert_eq!(
+            ruby_rect(RubyPosition::Right, RubyAlign::End),
+            Rect::new(15.0, 10.0, 25.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn test_widest_section_rect() {
+        let mut layout_info = single_line_layout(&[("漢", 10.0), ("字", 10.0)], 20.0);
+        layout_info.section_rects = vec![
+            (Entity::PLACEHOLDER, Rect::new(30.0, 0.0, 40.0, 20.0)),
+            (Entity::PLACEHOLDER, Rect::new(0.0, 20.0, 20.0, 40.0)),
+            (Entity::PLACEHOLDER, Rect::new(0.0, 40.0, 20.0, 60.0)),
+        ];
+
+        assert_eq!(
+            section_rect(
+                &TextBlock::new(&layout_info, &[]),
+                Entity::PLACEHOLDER,
+                SectionRectTieBreak::Widest
+            ),
+            Some(Rect::new(0.0, 20.0, 20.0, 40.0))
+        );
+    }
+
+    #[test]
+    fn test_section_rects_cache() {
+        let mut layout_info = single_line_layout(&[("漢", 10.0), ("字", 10.0)], 20.0);
+        let mut cache = SectionRectsCache::default();
+        let cached_rects =
+            |cache: &mut SectionRectsCache, layout_info: &TextLayoutInfo, changed| {
+                cache
+                    .get(Entity::PLACEHOLDER, layout_info, changed)
+                    .get(Entity::PLACEHOLDER)
+                    .to_vec()
+            };
+        assert_eq!(
+            cached_rects(&mut cache, &layout_info, false),
+            [Rect::new(0.0, 0.0, 20.0, 20.0)]
+        );
+
+        // Reindexed only when the layout changed
+        layout_info.section_rects[0].1 = Rect::new(0.0, 20.0, 20.0, 40.0);
+        assert_eq!(
+            cached_rects(&mut cache, &layout_info, false),
+            [Rect::new(0.0, 0.0, 20.0, 20.0)]
+        );
+        assert_eq!(
+            cached_rects(&mut cache, &layout_info, true),
+            [Rect::new(0.0, 20.0, 20.0, 40.0)]
+        );
+
+        // Forgotten once not looked up, as its changes may have been missed
+        cache.retain_used();
+        cache.retain_used();
+        layout_info.section_rects[0].1 = Rect::new(0.0, 40.0, 20.0, 60.0);
+        assert_eq!(
+            cached_rects(&mut cache, &layout_info, false),
+            [Rect::new(0.0, 40.0, 20.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_cap_top_anchor() {
+        // "Ab" on a 30px line with its baseline at 24px
+        let mut layout_info = single_line_layout(&[("A", 10.0), ("b", 10.0)], 30.0);
+        for glyph in &mut layout_info.glyphs {
+            glyph.size.y = 17.0;
+            glyph.atlas_info.location.offset.y = 17;
+            glyph.position.y = 24.0 - 17.0 + 17.0 / 2.0;
+        }
+        let lines = text_lines(["Ab"].into_iter());
+        let block = TextBlock::new(&layout_info, &lines);
+
+        let cap_top = cap_top(
+            &block,
+            Entity::PLACEHOLDER,
+            SectionRectTieBreak::default(),
+            17.0,
+        )
+        .unwrap();
+        assert_eq!(cap_top, 7.0);
+
+        let section_rect = base_rect(
+            &block,
+            Entity::PLACEHOLDER,
+            SectionRectTieBreak::default(),
+            RubyPosition::Over,
+        )
+        .unwrap();
+        let ruby = Ruby {
+            anchor: RubyAnchor::CapHeight,
+            ..default()
+        };
+        let mut ruby_box = RubyBox::new(&ruby, section_rect, Vec2::new(20.0, 10.0));
+        assert_eq!(ruby_box.center.y, 0.0);
+        ruby_box.anchor_to_cap_top(cap_top);
+        assert_eq!(ruby_box.rect().max.y, cap_top);
+    }
+
+    #[test]
+    fn test_fixed_gap() {
+        // "Ab" on a 30px line with its baseline at 24px and cap height of 17px
+        let mut layout_info = single_line_layout(&[("A", 10.0), ("b", 10.0)], 30.0);
+        for glyph in &mut layout_info.glyphs {
+            glyph.size.y = 17.0;
+            glyph.atlas_info.location.offset.y = 17;
+            glyph.position.y = 24.0 - 17.0 + 17.0 / 2.0;
+        }
+        let lines = text_lines(["Ab"].into_iter());
+        let ruby_rect = |position, cap_height| {
+            let ruby = Ruby {
+                position,
+                gap: RubyGapMode::Fixed(3.0),
+                ..Ruby::new("ルビ")
+            };
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &lines),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(20.0, 10.0),
+                cap_height,
+                None,
+                20.0,
+            )
+            .unwrap()
+            .rect()
+        };
+
+        assert_eq!(ruby_rect(RubyPosition::Over, Some(17.0)).max.y, 4.0);
+        assert_eq!(ruby_rect(RubyPosition::Under, Some(17.0)).min.y, 27.0);
+        // Without the cap height metric, the gap is from the top of the line box
+        assert_eq!(ruby_rect(RubyPosition::Over, None).max.y, -3.0);
+    }
+
+    #[test]
+    fn test_under_ruby_clears_descenders() {
+        // "gy" on a 30px line with its baseline at 24px
+        let mut layout_info = single_line_layout(&[("g", 10.0), ("y", 10.0)], 30.0);
+        for glyph in &mut layout_info.glyphs {
+            glyph.size.y = 17.0;
+            glyph.atlas_info.location.offset.y = 12;
+            glyph.position.y = 24.0 - 12.0 + 17.0 / 2.0;
+        }
+        let lines = text_lines(["gy"].into_iter());
+        let ruby = Ruby {
+            position: RubyPosition::Under,
+            ..Ruby::new("ルビ")
+        };
+        let ruby_top = |descender| {
+            place_ruby(
+                &ruby,
+                &TextBlock::new(&layout_info, &lines),
+                Entity::PLACEHOLDER,
+                &FuriganaSettings::default(),
+                Vec2::new(20.0, 10.0),
+                None,
+                descender,
+                20.0,
+            )
+            .unwrap()
+            .rect()
+            .min
+            .y
+        };
+
+        // Centered on the bottom of the line box
+        assert_eq!(ruby_top(None), 25.0);
+        assert_eq!(ruby_top(Some(7.0)), 31.0);
+        // Already clear
+        assert_eq!(ruby_top(Some(1.0)), 25.0);
+    }
+
+    #[test]
+    fn test_ruby_vertical_align() {
+        let section_rect = Rect::new(0.0, 20.0, 40.0, 50.0);
+        let ruby_size = Vec2::new(20.0, 10.0);
+        let ruby_rect = |position, vertical_align| {
+            let ruby = Ruby {
+                position,
+                vertical_align,
+                ..default()
+            };
+            RubyBox::new(&ruby, section_rect, ruby_size).rect()
+        };
+
+        use RubyPosition::*;
+        use RubyVerticalAlign::*;
+        assert_eq!(ruby_rect(Over, Center).min.y, 15.0);
+        assert_eq!(ruby_rect(Over, Outside).max.y, 20.0);
+        assert_eq!(ruby_rect(Over, Inside).min.y, 20.0);
+        assert_eq!(ruby_rect(Under, Center).min.y, 45.0);
+        assert_eq!(ruby_rect(Under, Outside).min.y, 50.0);
+        assert_eq!(ruby_rect(Under, Inside).max.y, 50.0);
+    }
+}
@@ -1,21 +1,114 @@
 //! Naive implementation of [Ruby characters](https://en.wikipedia.org/wiki/Ruby_character) for UI and 2D Text in Bevy.
+mod aozora;
+mod commands;
+mod font_metrics;
+mod html;
+mod layout;
+mod links;
+mod merge;
+mod placement;
+mod reading;
+mod register;
+mod reveal;
+mod rubies;
 #[cfg(feature = "text2d")]
 mod text2d;
 mod ui;
 
-use bevy::{ecs::query::QueryData, prelude::*};
+use core::{f32::consts::FRAC_1_SQRT_2, time::Duration};
+use std::borrow::Cow;
 
+use bevy::{
+    ecs::{
+        entity::{EntityHashMap, EntityHashSet},
+        query::QueryData,
+        relationship::{Relationship, RelationshipTarget},
+        system::SystemParam,
+    },
+    platform::collections::HashSet,
+    prelude::*,
+    text::{LineHeight, TextLayoutInfo},
+};
+use layout::{
+    RubyBox, TextBlock, center_mono_readings, mono_base_centers, place_ruby,
+    separate_adjacent_lines, stack_same_side,
+};
+use merge::merged_base_ruby;
+use placement::RubySettling;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[doc(hidden)]
+pub use aozora::IntoReading;
+pub use aozora::{parse_aozora, spawn_furigana_spans};
+pub use commands::RubyCommandsExt;
+pub use html::to_ruby_html;
+pub use links::{RubyLinks, ruby_text_entity};
+pub use merge::{MergedRuby, RubyMergedInto};
+pub use placement::{
+    RubyPlacement, RubyPlacementProvider, RubyPositioned, RubyStabilized, RubyTextSpawned,
+    compute_placement_now, debug_placement, place_ruby_in_layout, ruby_window_rect,
+    section_layout_info, translation_window_rect,
+};
+pub use reading::reading_text_of;
+pub use register::RubyAppExt;
+pub use reveal::RubyReveal;
+pub use rubies::{Rubies, RubyEntry};
 #[cfg(feature = "text2d")]
 pub use text2d::{LinkedRubyText2d, RubyText2d};
 pub use ui::{LinkedRubyText, RubyText};
 
 pub struct FuriganaPlugin;
 
+/// System sets of [`FuriganaPlugin`], all in `PostUpdate`.
+///
+/// Ruby text entities themselves are spawned by observers as soon as a [`Ruby`] is added.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FuriganaSystems {
+    /// Syncs ruby text (content, font, color and visibility) with its [`Ruby`], before text
+    /// layout.
+    Spawn,
+    /// Places ruby text against its base text. For UI text this runs after UI layout
+    /// (`UiSystems::PostLayout`); for `Text2d`, before 2D text layout (`Text2dUpdateSystems`),
+    /// except for the ruby background.
+    Update,
+}
+
 impl Plugin for FuriganaPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<FuriganaSettings>();
+        app.init_resource::<FuriganaSettings>()
+            .init_resource::<RubyPalette>()
+            .init_resource::<CheckedRubyFonts>()
+            .add_message::<RubyTextSpawned>()
+            .add_message::<RubyPositioned>()
+            .add_message::<RubyStabilized>()
+            .register_type::<Ruby>()
+            .register_type::<RubyPosition>()
+            .register_type::<RubyAlign>()
+            .register_type::<RubyOutline>()
+            .register_type::<RubyColorRef>()
+            .register_type::<RubyReveal>()
+            .configure_sets(
+                PostUpdate,
+                (FuriganaSystems::Spawn, FuriganaSystems::Update).chain(),
+            );
 
-        app.add_plugins(ui::plugin);
+        app.add_plugins((ui::plugin, rubies::plugin));
+
+        app.add_systems(
+            PostUpdate,
+            (
+                check_ruby_font_family::<RubyText>,
+                check_ruby_font_family::<RubyTranslationText>,
+                check_ruby_font_family::<RubyStackText>,
+                #[cfg(feature = "text2d")]
+                check_ruby_font_family::<RubyText2d>,
+            )
+                .in_set(FuriganaSystems::Spawn),
+        )
+        .add_systems(
+            PostUpdate,
+            resize_ruby_stacks.before(FuriganaSystems::Spawn),
+        );
 
         #[cfg(feature = "text2d")]
         app.add_plugins(text2d::plugin);
@@ -26,27 +119,421 @@ impl Plugin for FuriganaPlugin {
 pub struct FuriganaSettings {
     /// Update `GlobalUiTransform` to eliminate one-frame delay.
     pub update_ui_global_transform: bool,
+    /// Duration over which newly spawned ruby text fades in. If `None`, ruby text appears immediately.
+    pub ruby_fade_in: Option<Duration>,
+    /// Range that every [`Ruby::font_size_scale`] is clamped to.
+    pub ruby_font_size_scale_range: (f32, f32),
+    /// Multiplier applied to the font size of all ruby text, on top of [`Ruby::font_size_scale`]
+    /// (e.g. for accessibility zoom).
+    pub ruby_font_size_multiplier: f32,
+    /// Round the font size of ruby text to whole logical pixels, as glyphs rendered at a fractional
+    /// size (e.g. of a base font size times [`Ruby::font_size_scale`]) can look blurry.
+    pub round_ruby_font_size: bool,
+    /// Render ruby text behind the base text instead of in front of it.
+    /// Applied when ruby text is spawned.
+    pub ruby_behind_base: bool,
+    /// Spawn 2D ruby text as a child of its base `Text2d` (of the text root, for spans), placed in
+    /// its local space, instead of as a top-level entity. Applied when ruby text is spawned.
+    ///
+    /// UI ruby text is unaffected, as a UI text node with children is no longer sized by its text.
+    pub ruby_2d_child_of_base: bool,
+    /// Font for ruby text. If `None`, ruby text uses the font of the base text.
+    ///
+    /// Bevy doesn't report glyphs missing from a font, so set this to a font covering the readings
+    /// (e.g. kana) when the base font lacks them.
+    pub ruby_font: Option<Handle<Font>>,
+    /// Replace ruby text color with black or white when it has low contrast against the background
+    /// of the base text (its `TextBackgroundColor`, or [`Self::ruby_contrast_background`]).
+    pub ruby_auto_contrast: bool,
+    /// Background color assumed by [`Self::ruby_auto_contrast`] for base text without
+    /// `TextBackgroundColor`. If `None`, such ruby text is left as is.
+    pub ruby_contrast_background: Option<Color>,
+    /// If set, 2D ruby text of base text with `TextBackgroundColor` gets a background box of the
+    /// same color, padded by this amount on each side.
+    pub ruby_background_padding: Option<Vec2>,
+    /// Maximum number of ruby text entities (UI and 2D combined). Once reached, no more ruby text
+    /// is spawned and a warning is logged. If `None`, there is no limit.
+    pub max_ruby_entities: Option<usize>,
+    /// Warn (once per pair of fonts) when ruby text uses a font of a different family than its
+    /// base text, e.g. because of [`Self::ruby_font`].
+    pub warn_ruby_font_mismatch: bool,
+    /// Push [`RubyPosition::Under`] ruby text below the descender line of the base text font, so
+    /// that it clears descenders (e.g. of "gy"). Not applied with [`RubyGapMode::Fixed`].
+    pub ruby_clear_descenders: bool,
+    /// Which rect of a text section to annotate when the section is laid out as several rects
+    /// (e.g. when it wraps).
+    pub section_rect_tie_break: SectionRectTieBreak,
+    /// Offset in logical pixels (Y down) added to the placement of all ruby text, before it is
+    /// snapped to whole pixels. Use it to fine-tune the alignment against a font's quirks.
+    pub subpixel_nudge: Vec2,
+    /// Smallest scale that [`Ruby::max_width`] shrinks ruby text to. Ruby that still doesn't fit
+    /// overflows its limit.
+    pub ruby_min_fit_scale: f32,
+    /// How to show ruby whose [`Ruby::rt`] is empty or whitespace only.
+    pub blank_ruby: BlankRuby,
+    /// Merge the ruby of adjacent single-character `TextSpan`s with identically styled [`Ruby`]
+    /// (and font and color) into one ruby text spanning them, to spawn fewer entities.
+    ///
+    /// Spans are merged as they are spawned; see [`RubyMergedInto`].
+    pub merge_adjacent_ruby: bool,
+    /// Only show ruby whose base text contains CJK characters (Han, kana or Hangul), hiding ruby
+    /// on purely Latin (or other non-CJK) base text, e.g. in an app mixing Japanese and English.
+    pub cjk_only: bool,
 }
 
 impl Default for FuriganaSettings {
     fn default() -> Self {
         Self {
             update_ui_global_transform: true,
+            ruby_fade_in: None,
+            ruby_font_size_scale_range: (0.1, 2.0),
+            ruby_font_size_multiplier: 1.0,
+            round_ruby_font_size: false,
+            ruby_behind_base: false,
+            ruby_2d_child_of_base: false,
+            ruby_font: None,
+            ruby_auto_contrast: false,
+            ruby_contrast_background: None,
+            ruby_background_padding: None,
+            max_ruby_entities: None,
+            warn_ruby_font_mismatch: false,
+            ruby_clear_descenders: false,
+            subpixel_nudge: Vec2::ZERO,
+            ruby_min_fit_scale: 0.5,
+            blank_ruby: BlankRuby::default(),
+            merge_adjacent_ruby: false,
+            cjk_only: false,
+            section_rect_tie_break: SectionRectTieBreak::default(),
+        }
+    }
+}
+
+/// Filter for ruby, translation and stacked annotation text entities of any kind.
+#[cfg(feature = "text2d")]
+type AnyRubyText = Or<(
+    With<RubyText>,
+    With<RubyText2d>,
+    With<RubyTranslationText>,
+    With<RubyStackText>,
+)>;
+#[cfg(not(feature = "text2d"))]
+type AnyRubyText = Or<(
+    With<RubyText>,
+    With<RubyTranslationText>,
+    With<RubyStackText>,
+)>;
+
+/// Returns whether spawning another ruby text would exceed [`FuriganaSettings::max_ruby_entities`].
+fn ruby_budget_exceeded(settings: &FuriganaSettings, ruby_text_count: usize) -> bool {
+    let Some(max) = settings.max_ruby_entities else {
+        return false;
+    };
+    if ruby_text_count < max {
+        return false;
+    }
+    warn_once!(
+        "Reached FuriganaSettings::max_ruby_entities ({max}); no more ruby text will be spawned"
+    );
+    true
+}
+
+/// Pairs of base and ruby fonts already compared by [`check_ruby_font_family`].
+#[derive(Resource, Default)]
+struct CheckedRubyFonts(HashSet<(AssetId<Font>, AssetId<Font>)>);
+
+/// Warns if the font family of ruby text linked by `L` differs from that of its base text, with
+/// [`FuriganaSettings::warn_ruby_font_mismatch`].
+fn check_ruby_font_family<L: Relationship>(
+    ruby_text: Query<(&L, &TextFont)>,
+    text_fonts: Query<&TextFont>,
+    fonts: Res<Assets<Font>>,
+    settings: Res<FuriganaSettings>,
+    mut checked: ResMut<CheckedRubyFonts>,
+) {
+    if !settings.warn_ruby_font_mismatch {
+        return;
+    }
+
+    for (link, ruby_font) in &ruby_text {
+        let Ok(text_font) = text_fonts.get(link.get()) else {
+            continue;
+        };
+        let pair = (text_font.font.id(), ruby_font.font.id());
+        if pair.0 == pair.1 || checked.0.contains(&pair) {
+            continue;
+        }
+        // Checked once both fonts are loaded
+        let (Some(base), Some(ruby)) = (fonts.get(pair.0), fonts.get(pair.1)) else {
+            continue;
+        };
+        checked.0.insert(pair);
+
+        let base_family = font_metrics::family_name(&base.data);
+        let ruby_family = font_metrics::family_name(&ruby.data);
+        if base_family != ruby_family {
+            warn!(
+                "Ruby text font family {ruby_family:?} differs from its base text font family {base_family:?}"
+            );
         }
     }
 }
 
+/// Returns the cap height in pixels of `text_font` of the base text, if `ruby` is anchored to it
+/// and the font is loaded and has the metric.
+fn base_cap_height(ruby: &Ruby, text_font: Option<&TextFont>, fonts: &Assets<Font>) -> Option<f32> {
+    if ruby.anchor != RubyAnchor::CapHeight && !matches!(ruby.gap, RubyGapMode::Fixed(_)) {
+        return None;
+    }
+    let text_font = text_font?;
+    let font = fonts.get(&text_font.font)?;
+    Some(font_metrics::cap_height(&font.data)? * text_font.font_size)
+}
+
+/// Returns the descender in pixels of `text_font` of the base text, if `ruby` is to clear it
+/// and the font is loaded and has the metric.
+fn base_descender(
+    ruby: &Ruby,
+    text_font: Option<&TextFont>,
+    fonts: &Assets<Font>,
+    settings: &FuriganaSettings,
+) -> Option<f32> {
+    if !settings.ruby_clear_descenders
+        || ruby.position != RubyPosition::Under
+        || ruby.gap != RubyGapMode::Auto
+    {
+        return None;
+    }
+    let text_font = text_font?;
+    let font = fonts.get(&text_font.font)?;
+    Some(font_metrics::descender(&font.data)? * text_font.font_size)
+}
+
+/// A ruby of a text block to place with [`place_block_rubies`]: the entity of its base text, the
+/// [`Ruby`] (or translation), the spans merged into it, and the size of its ruby text in physical
+/// pixels, if laid out.
+type BlockRuby<'a> = (Entity, &'a Ruby, Option<&'a MergedRuby>, Option<Vec2>);
+
+/// Places the rubies of a text block against their bases and against each other, for both placing
+/// the ruby text and [`compute_placement_now`].
+///
+/// Returns the box of each of `rubies` in physical pixels, or `None` for ruby not to be shown: on
+/// `non_cjk` sections (see [`non_cjk_sections`]), without laid out ruby text, or on base text not
+/// laid out yet (e.g. the font is still loading).
+fn place_block_rubies(
+    block: &TextBlock,
+    rubies: &[BlockRuby],
+    non_cjk: &EntityHashSet,
+    text_fonts: &Query<&TextFont>,
+    fonts: &Assets<Font>,
+    settings: &FuriganaSettings,
+) -> Vec<Option<RubyBox>> {
+    let scale_factor = block.layout_info.scale_factor;
+    let mut ruby_boxes = rubies
+        .iter()
+        .map(|&(base, ruby, merged, size)| {
+            if non_cjk.contains(&base) {
+                return None;
+            }
+            let ruby = merged_base_ruby(ruby, base, merged, block, settings.section_rect_tie_break);
+            let text_font = text_fonts.get(base).ok();
+            let cap_height = base_cap_height(&ruby, text_font, fonts);
+            let descender = base_descender(&ruby, text_font, fonts, settings);
+            place_ruby(
+                &ruby,
+                block,
+                base,
+                settings,
+                size?,
+                cap_height.map(|cap_height| cap_height * scale_factor),
+                descender.map(|descender| descender * scale_factor),
+                text_font.map_or(0.0, |font| font.font_size) * scale_factor,
+            )
+            .map(|ruby_box| ruby_box.nudged(settings.subpixel_nudge * scale_factor))
+        })
+        .collect::<Vec<_>>();
+
+    let mut boxes = ruby_boxes.iter().flatten().copied().collect::<Vec<_>>();
+    stack_same_side(&mut boxes);
+    separate_adjacent_lines(&mut boxes);
+    for (ruby_box, laid_out) in ruby_boxes.iter_mut().flatten().zip(boxes) {
+        *ruby_box = laid_out;
+    }
+    ruby_boxes
+}
+
+/// Centers of the characters of the base text to center each reading of [`RubyMode::Mono`] ruby
+/// over with [`center_mono_ruby`], or `None` for ruby placed as a group: over merged spans or an
+/// overridden base, or without a character per reading on the annotated line.
+fn mono_ruby_base_centers(
+    block: &TextBlock,
+    base: Entity,
+    ruby: &Ruby,
+    merged: Option<&MergedRuby>,
+    tie_break: SectionRectTieBreak,
+) -> Option<Vec<f32>> {
+    if merged.is_some() || ruby.base_rect_override.is_some() {
+        return None;
+    }
+    let count = ruby.mono_readings()?.len();
+    mono_base_centers(block, base, tie_break, count)
+}
+
+/// Centers each reading of [`RubyMode::Mono`] ruby placed in `ruby_box` (in physical pixels) over
+/// its character at `base_centers`, by moving the glyphs of its ruby text laid out around
+/// `origin_x`. Returns whether any glyph was moved.
+fn center_mono_ruby(
+    ruby: &Ruby,
+    base_centers: &[f32],
+    ruby_box: &RubyBox,
+    ruby_layout_info: &mut TextLayoutInfo,
+    origin_x: f32,
+) -> bool {
+    let Some(readings) = ruby.mono_readings() else {
+        return false;
+    };
+    let centers = base_centers
+        .iter()
+        .map(|center| origin_x + (center - ruby_box.center.x) / ruby_box.scale);
+    center_mono_readings(ruby_layout_info, &readings, centers)
+}
+
+/// Returns the font of ruby text for base text of `text_font`.
+///
+/// Fields not specific to ruby (e.g. `font_smoothing`, or letter spacing once `TextFont` has it)
+/// are inherited from the base text, so that the ruby is rendered in the same style.
+fn ruby_text_font(ruby: &Ruby, text_font: &TextFont, settings: &FuriganaSettings) -> TextFont {
+    let (min_scale, max_scale) = settings.ruby_font_size_scale_range;
+    let font_size = text_font.font_size
+        * ruby.font_size_scale.clamp(min_scale, max_scale)
+        * settings.ruby_font_size_multiplier;
+    TextFont {
+        font: ruby
+            .font
+            .as_ref()
+            .or(settings.ruby_font.as_ref())
+            .unwrap_or(&text_font.font)
+            .clone(),
+        font_size: if settings.round_ruby_font_size {
+            // Not rounded down to nothing
+            font_size.round().max(1.0)
+        } else {
+            font_size
+        },
+        // A line height of the base text (e.g. `LineHeight::Px`) would pad the single-line ruby
+        // text box and make it float away from the base
+        line_height: LineHeight::default(),
+        ..text_font.clone()
+    }
+}
+
+fn ruby_text_color(
+    ruby: &Ruby,
+    text_color: TextColor,
+    background: Option<&TextBackgroundColor>,
+    palette: &RubyPalette,
+    settings: &FuriganaSettings,
+) -> TextColor {
+    let ruby_color = ruby.resolved_color(palette);
+    // Color emoji glyphs are tinted by the text color, so inheriting it would discolor them
+    if ruby_color.is_none() && ruby.is_emoji() {
+        return TextColor(Color::WHITE.with_alpha(text_color.0.alpha()));
+    }
+
+    let color = match ruby_color {
+        Some(TextColor(color)) if ruby.inherit_alpha => {
+            TextColor(color.with_alpha(color.alpha() * text_color.0.alpha()))
+        }
+        Some(color) => color,
+        None => text_color,
+    };
+    if !settings.ruby_auto_contrast {
+        return color;
+    }
+
+    match background
+        .map(|background| background.0)
+        .or(settings.ruby_contrast_background)
+    {
+        Some(background) => TextColor(contrasting_color(color.0, background)),
+        None => color,
+    }
+}
+
+/// Returns `color`, or black or white (whichever contrasts more) if `color` has low contrast
+/// against `background`.
+fn contrasting_color(color: Color, background: Color) -> Color {
+    // WCAG AA for normal text
+    const MIN_CONTRAST_RATIO: f32 = 4.5;
+
+    let contrast_ratio = |a: Color, b: Color| {
+        let a = LinearRgba::from(a).luminance();
+        let b = LinearRgba::from(b).luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    };
+
+    if contrast_ratio(color, background) >= MIN_CONTRAST_RATIO {
+        return color;
+    }
+
+    let adjusted =
+        if contrast_ratio(Color::BLACK, background) >= contrast_ratio(Color::WHITE, background) {
+            Color::BLACK
+        } else {
+            Color::WHITE
+        };
+    adjusted.with_alpha(color.alpha())
+}
+
 /// Component to add ruby text to a `Text`, `Text2d`, or `TextSpan`.
-#[derive(Component, Clone, Debug)]
+///
+/// If the base text wraps to multiple lines, the ruby text is placed over (or under) its first line,
+/// or another one chosen by [`FuriganaSettings::section_rect_tie_break`].
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component, Default)]
 pub struct Ruby {
     /// Ruby text.
     pub rt: String,
     pub position: RubyPosition,
     pub align: RubyAlign,
+    pub mode: RubyMode,
+    pub anchor: RubyAnchor,
+    pub vertical_align: RubyVerticalAlign,
+    pub word_spacing: RubyWordSpacing,
+    pub gap: RubyGapMode,
     /// Font size relative to this text's font size. (e.g. 0.5 for half size)
     pub font_size_scale: f32,
-    /// Color for ruby text. If `None`, inherits the color of the base text.
+    /// Color for ruby text. If `None`, inherits the color of the base text, except for emoji-only
+    /// ruby text, which keeps the colors of a color emoji font.
     pub color: Option<TextColor>,
+    /// Color for ruby text looked up at runtime (e.g. for theming), taking precedence over
+    /// [`Self::color`]. Falls back to [`Self::color`] if the lookup fails.
+    pub color_ref: Option<RubyColorRef>,
+    /// Multiply the alpha of an explicit [`Self::color`] (or [`Self::color_ref`]) by that of the
+    /// base text color, so that the ruby fades along with the base text. An inherited color
+    /// follows the base text anyway.
+    pub inherit_alpha: bool,
+    /// Font for ruby text (e.g. a lighter weight). If `None`, falls back to
+    /// [`FuriganaSettings::ruby_font`], then to the font of the base text.
+    pub font: Option<Handle<Font>>,
+    /// Drop shadow for ruby text.
+    pub shadow: Option<RubyShadow>,
+    /// Outline for ruby text, to keep it legible over busy backgrounds.
+    pub outline: Option<RubyOutline>,
+    /// Width limit for ruby text, which is scaled down to fit (e.g. a long reading over a short
+    /// base), so that it doesn't collide with neighboring ruby.
+    pub max_width: RubyMaxWidth,
+    /// Offset to fine-tune the placement, with X along the base text and positive Y away from it
+    /// (e.g. to keep ruby off a font's tall glyphs), in [`Self::offset_unit`].
+    pub offset: Vec2,
+    pub offset_unit: RubyOffsetUnit,
+    /// Display the reading with its graphemes in reverse order (e.g. for mirrored text), keeping
+    /// [`Self::rt`] as is.
+    pub reversed: bool,
+    /// Overrides the base text bounds used for placement.
+    /// If `None`, the bounds are taken from the section rect of the base text in `TextLayoutInfo`.
+    ///
+    /// The rect is in the same coordinate space as `TextLayoutInfo::section_rects`.
+    pub base_rect_override: Option<Rect>,
 }
 
 impl Ruby {
@@ -55,10 +542,201 @@ impl Ruby {
             rt: rt.into(),
             position: RubyPosition::default(),
             align: RubyAlign::default(),
+            mode: RubyMode::default(),
+            anchor: RubyAnchor::default(),
+            vertical_align: RubyVerticalAlign::default(),
+            word_spacing: RubyWordSpacing::default(),
+            gap: RubyGapMode::default(),
             font_size_scale: 0.5,
             color: None,
+            color_ref: None,
+            inherit_alpha: true,
+            font: None,
+            shadow: None,
+            outline: None,
+            max_width: RubyMaxWidth::None,
+            offset: Vec2::ZERO,
+            offset_unit: RubyOffsetUnit::default(),
+            reversed: false,
+            base_rect_override: None,
         }
     }
+
+    /// Mono-ruby with a reading per character of the base text (see [`RubyMode::Mono`]).
+    pub fn mono(readings: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let readings = readings
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<String>>();
+        Self {
+            mode: RubyMode::Mono(readings.clone()),
+            ..Self::new(readings.concat())
+        }
+    }
+
+    /// Places the ruby over the base text ([`RubyPosition::Over`]).
+    pub fn over(self) -> Self {
+        Self {
+            position: RubyPosition::Over,
+            ..self
+        }
+    }
+
+    /// Places the ruby under the base text ([`RubyPosition::Under`]).
+    pub fn under(self) -> Self {
+        Self {
+            position: RubyPosition::Under,
+            ..self
+        }
+    }
+
+    /// Sets [`Self::align`].
+    pub fn align(self, align: RubyAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    /// Sets [`Self::font_size_scale`].
+    pub fn scale(self, font_size_scale: f32) -> Self {
+        Self {
+            font_size_scale,
+            ..self
+        }
+    }
+
+    /// Sets [`Self::color`].
+    pub fn color(self, color: impl Into<Color>) -> Self {
+        Self {
+            color: Some(TextColor(color.into())),
+            ..self
+        }
+    }
+
+    /// Sets [`Self::offset`].
+    pub fn offset(self, offset: Vec2) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// Color of the ruby text, with [`Self::color_ref`] looked up in `palette`, or `None` to
+    /// inherit the color of the base text.
+    pub(crate) fn resolved_color(&self, palette: &RubyPalette) -> Option<TextColor> {
+        let referenced = self.color_ref.and_then(|color_ref| match color_ref {
+            RubyColorRef::Palette(index) => palette.0.get(index).copied().map(TextColor),
+        });
+        referenced.or(self.color)
+    }
+
+    /// Ruby text as displayed, with [`Self::word_spacing`] and [`Self::reversed`] applied. Beside
+    /// vertical text, it is stacked one character per line instead.
+    pub(crate) fn display_text(&self, blank: BlankRuby) -> Cow<'_, str> {
+        if self.is_blank() {
+            return Cow::Borrowed(match blank {
+                BlankRuby::Skip => "",
+                BlankRuby::ThinSpace => "\u{2009}",
+            });
+        }
+
+        if let Some(readings) = self.mono_readings() {
+            return Cow::Owned(readings.concat());
+        }
+
+        let text = self.spaced_text();
+        if self.reversed {
+            Cow::Owned(text.graphemes(true).rev().collect())
+        } else {
+            text
+        }
+    }
+
+    /// Readings of [`RubyMode::Mono`] ruby as displayed, which make up its ruby text, or `None`
+    /// for ruby placed as a group.
+    pub(crate) fn mono_readings(&self) -> Option<Vec<Cow<'_, str>>> {
+        let RubyMode::Mono(readings) = &self.mode else {
+            return None;
+        };
+        if self.position.is_beside() {
+            return None;
+        }
+
+        let readings = readings.iter().map(|reading| {
+            if self.reversed {
+                Cow::Owned(reading.graphemes(true).rev().collect())
+            } else {
+                Cow::Borrowed(reading.as_str())
+            }
+        });
+        Some(readings.collect())
+    }
+
+    fn spaced_text(&self) -> Cow<'_, str> {
+        if self.position.is_beside() {
+            let chars = self.rt.chars().filter(|c| !c.is_whitespace());
+            return Cow::Owned(chars.map(String::from).collect::<Vec<_>>().join("\n"));
+        }
+
+        let space = match self.word_spacing {
+            RubyWordSpacing::Normal => return Cow::Borrowed(&self.rt),
+            RubyWordSpacing::Thin => "\u{2009}",
+            RubyWordSpacing::Hair => "\u{200A}",
+            RubyWordSpacing::None => "",
+        };
+        Cow::Owned(self.rt.replace(' ', space))
+    }
+
+    /// Whether [`Ruby::rt`] is empty or whitespace only. See [`FuriganaSettings::blank_ruby`].
+    pub(crate) fn is_blank(&self) -> bool {
+        self.rt.trim().is_empty()
+    }
+
+    /// Whether [`Ruby::rt`] consists of emoji only, which color emoji fonts render in their own
+    /// colors.
+    pub(crate) fn is_emoji(&self) -> bool {
+        !self.is_blank()
+            && self
+                .rt
+                .graphemes(true)
+                .filter(|grapheme| !grapheme.trim().is_empty())
+                .all(is_emoji)
+    }
+}
+
+/// Returns the sections of a text root whose text has no CJK characters, whose ruby is hidden with
+/// [`FuriganaSettings::cjk_only`].
+fn non_cjk_sections<'a>(
+    sections: impl Iterator<Item = (Entity, &'a str)>,
+    settings: &FuriganaSettings,
+) -> EntityHashSet {
+    if !settings.cjk_only {
+        return EntityHashSet::default();
+    }
+    sections
+        .filter(|(_, text)| !text.chars().any(is_cjk))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Rough check for a CJK character: Han (including iteration marks), kana, Bopomofo or Hangul.
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{11FF}'
+            | '\u{2E80}'..='\u{2FDF}'
+            | '\u{3005}'..='\u{3007}'
+            | '\u{3040}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF66}'..='\u{FF9F}'
+            | '\u{20000}'..='\u{3FFFF}'
+    )
+}
+
+/// Rough check for a grapheme presented as emoji: one in the emoji blocks of the supplementary
+/// planes (including flags), or one with the emoji variation selector.
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .any(|c| matches!(c, '\u{1F000}'..='\u{1FAFF}' | '\u{FE0F}'))
 }
 
 impl Default for Ruby {
@@ -67,7 +745,357 @@ impl Default for Ruby {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// Marker component to suppress ruby text of a [`Ruby`] on the same entity.
+///
+/// The ruby text is not spawned while this is present, but the [`Ruby`] itself is kept as is.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct NoRuby;
+
+/// Marker component to hide all ruby within a text root, e.g. to turn furigana off for one
+/// paragraph only.
+///
+/// Put this on the `Text` or `Text2d` root (or any of its ancestors); ruby of its spans is
+/// hidden too. The ruby text is kept, so removing this shows it again.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct FuriganaDisabled;
+
+/// Returns whether `text_root` or any of its ancestors has [`FuriganaDisabled`].
+fn furigana_disabled(
+    text_root: Entity,
+    ancestors: &Query<&ChildOf>,
+    disabled: &Query<(), With<FuriganaDisabled>>,
+) -> bool {
+    disabled.contains(text_root)
+        || ancestors
+            .iter_ancestors(text_root)
+            .any(|ancestor| disabled.contains(ancestor))
+}
+
+/// Returns whether `entity` is hidden by its own [`Visibility`] or that of an ancestor.
+///
+/// Unlike [`InheritedVisibility`], this doesn't lag behind until visibility has been propagated.
+fn hidden_in_hierarchy(
+    entity: Entity,
+    ancestors: &Query<&ChildOf>,
+    visibility: impl Fn(Entity) -> Option<Visibility>,
+) -> bool {
+    for entity in core::iter::once(entity).chain(ancestors.iter_ancestors(entity)) {
+        match visibility(entity) {
+            Some(Visibility::Hidden) => return true,
+            Some(Visibility::Visible) => return false,
+            Some(Visibility::Inherited) | None => {}
+        }
+    }
+    false
+}
+
+/// Returns whether ruby of `text_root` is hidden, by [`FuriganaDisabled`] or by the [`Visibility`]
+/// of the text root or an ancestor.
+fn root_ruby_hidden(
+    text_root: Entity,
+    ancestors: &Query<&ChildOf>,
+    disabled: &Query<(), With<FuriganaDisabled>>,
+    visibility: impl Fn(Entity) -> Option<Visibility>,
+) -> bool {
+    furigana_disabled(text_root, ancestors, disabled)
+        || hidden_in_hierarchy(text_root, ancestors, visibility)
+}
+
+/// Text roots whose ruby was placed by the last run of `update_ruby` or `update_ruby_2d`, with
+/// their number of rubies, so that placing it again can be skipped while static.
+#[derive(Default)]
+pub(crate) struct PlacedRoots {
+    last: EntityHashMap<usize>,
+    current: EntityHashMap<usize>,
+}
+
+impl PlacedRoots {
+    /// Returns whether the `ruby_count` rubies of `text_root` are still in place from the last run,
+    /// given whether anything they are placed by `changed` since then.
+    fn unchanged(&mut self, text_root: Entity, ruby_count: usize, changed: bool) -> bool {
+        let unchanged = !changed && self.last.get(&text_root) == Some(&ruby_count);
+        if unchanged {
+            self.current.insert(text_root, ruby_count);
+        }
+        unchanged
+    }
+
+    /// Records that the `ruby_count` rubies of `text_root` have been placed in this run.
+    fn placed(&mut self, text_root: Entity, ruby_count: usize) {
+        self.current.insert(text_root, ruby_count);
+    }
+
+    /// Forgets the text roots not placed in this run, e.g. hidden ones, to place them once shown.
+    fn finish_run(&mut self) {
+        self.last = core::mem::take(&mut self.current);
+    }
+}
+
+/// Component to add a translation to a `Text`, `Text2d`, or `TextSpan`, shown in addition to
+/// its [`Ruby`] reading, e.g. the reading over the base text and the translation under it.
+///
+/// The translation is placed like ruby text according to the wrapped [`Ruby`], whose `rt` is the
+/// translation text. On the same side as the reading, it is stacked outside of it.
+#[derive(Component, Clone, Debug)]
+pub struct RubyTranslation(pub Ruby);
+
+impl RubyTranslation {
+    /// Creates a translation placed under the base text.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self(Ruby {
+            position: RubyPosition::Under,
+            ..Ruby::new(text)
+        })
+    }
+}
+
+/// Component for the translation text of [`RubyTranslation`], for both UI and 2D text.
+#[derive(Component, Clone, Copy)]
+#[require(RubyOverhang, RubySettling)]
+#[relationship(relationship_target = LinkedRubyTranslationText)]
+pub struct RubyTranslationText(
+    /// Entity of the corresponding `RubyTranslation` component.
+    pub Entity,
+);
+
+/// Tracks translation text entity corresponding to [`RubyTranslation`].
+#[derive(Component, Clone, Copy)]
+#[relationship_target(relationship = RubyTranslationText, linked_spawn)]
+pub struct LinkedRubyTranslationText(Entity);
+
+impl LinkedRubyTranslationText {
+    pub const fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Component to add any number of annotations to a `Text`, `Text2d`, or `TextSpan`, in addition
+/// to its [`Ruby`] reading and [`RubyTranslation`], e.g. a reading over the base text and a gloss
+/// and a note under it.
+///
+/// Each annotation gets its own annotation text, placed like ruby text according to its own
+/// [`Ruby`]. Annotations on the same side of the base are stacked away from it in order, after the
+/// reading and the translation.
+#[derive(Component, Clone, Debug, Default)]
+pub struct RubyStack(pub Vec<Ruby>);
+
+/// Component for an annotation text of [`RubyStack`], for both UI and 2D text.
+#[derive(Component, Clone, Copy)]
+#[require(RubyOverhang, RubySettling)]
+#[relationship(relationship_target = LinkedRubyStackTexts)]
+pub struct RubyStackText {
+    /// Entity of the corresponding `RubyStack` component.
+    #[relationship]
+    pub base: Entity,
+    /// Index of the annotation in the `RubyStack`.
+    pub index: usize,
+}
+
+/// Tracks the annotation text entities corresponding to [`RubyStack`].
+#[derive(Component)]
+#[relationship_target(relationship = RubyStackText, linked_spawn)]
+pub struct LinkedRubyStackTexts(Vec<Entity>);
+
+impl LinkedRubyStackTexts {
+    /// Annotation text entities, in no particular order; see [`RubyStackText::index`].
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+/// Component holding the [`Ruby`] annotations of a slot: [`Ruby`] itself for the reading,
+/// [`RubyTranslation`] for the translation, or [`RubyStack`] for any number of them.
+trait RubySlot: Component {
+    /// Whether adjacent annotations of this slot can be merged. See
+    /// [`FuriganaSettings::merge_adjacent_ruby`].
+    const MERGEABLE: bool;
+
+    /// Annotations of the slot, each shown by its own annotation text.
+    fn rubies(&self) -> &[Ruby];
+}
+
+impl RubySlot for Ruby {
+    const MERGEABLE: bool = true;
+
+    fn rubies(&self) -> &[Ruby] {
+        core::slice::from_ref(self)
+    }
+}
+
+impl RubySlot for RubyTranslation {
+    const MERGEABLE: bool = false;
+
+    fn rubies(&self) -> &[Ruby] {
+        core::slice::from_ref(&self.0)
+    }
+}
+
+impl RubySlot for RubyStack {
+    const MERGEABLE: bool = false;
+
+    fn rubies(&self) -> &[Ruby] {
+        &self.0
+    }
+}
+
+/// Annotation shown by an annotation text of a base.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Annotation {
+    /// Its [`Ruby`] reading.
+    Reading,
+    /// Its [`RubyTranslation`].
+    Translation,
+    /// An entry of its [`RubyStack`].
+    Stack(usize),
+}
+
+/// Base entity and annotation of an annotation text, linked by a ruby text relationship `R` (of UI
+/// or 2D text), a [`RubyTranslationText`] or a [`RubyStackText`].
+fn annotation_of<R: Relationship>(
+    (reading, translation, stack): (
+        Option<&R>,
+        Option<&RubyTranslationText>,
+        Option<&RubyStackText>,
+    ),
+) -> Option<(Entity, Annotation)> {
+    match (reading, translation, stack) {
+        (Some(reading), _, _) => Some((reading.get(), Annotation::Reading)),
+        (_, Some(&RubyTranslationText(base)), _) => Some((base, Annotation::Translation)),
+        (_, _, Some(&RubyStackText { base, index })) => Some((base, Annotation::Stack(index))),
+        (None, None, None) => None,
+    }
+}
+
+/// [`Ruby`] of `annotation` among the slots of a base, and whether its slot changed.
+fn annotation_ruby<'a>(
+    annotation: Annotation,
+    reading: Option<Ref<'a, Ruby>>,
+    translation: Option<Ref<'a, RubyTranslation>>,
+    stack: Option<Ref<'a, RubyStack>>,
+) -> Option<(bool, &'a Ruby)> {
+    match annotation {
+        Annotation::Reading => reading.map(|reading| (reading.is_changed(), reading.into_inner())),
+        Annotation::Translation => {
+            translation.map(|translation| (translation.is_changed(), &translation.into_inner().0))
+        }
+        Annotation::Stack(index) => stack.and_then(|stack| {
+            let changed = stack.is_changed();
+            Some((changed, stack.into_inner().0.get(index)?))
+        }),
+    }
+}
+
+/// Annotation texts linked by a ruby text relationship target `L` (of UI or 2D text), a
+/// [`LinkedRubyTranslationText`] and a [`LinkedRubyStackTexts`].
+fn linked_annotation_texts<L: RelationshipTarget<Collection = Entity>>(
+    (reading, translation, stack): (
+        Option<&L>,
+        Option<&LinkedRubyTranslationText>,
+        Option<&LinkedRubyStackTexts>,
+    ),
+) -> impl Iterator<Item = Entity> {
+    reading
+        .map(|reading| *reading.collection())
+        .into_iter()
+        .chain(translation.map(LinkedRubyTranslationText::entity))
+        .chain(
+            stack
+                .into_iter()
+                .flat_map(|stack| stack.entities().iter().copied()),
+        )
+}
+
+/// [`SystemParam`] to iterate the annotations placed outward of the readings, for both UI and 2D
+/// text: [`RubyTranslation`]s, then [`RubyStack`] entries in order.
+#[derive(SystemParam)]
+struct OuterAnnotations<'w, 's> {
+    translations: Query<
+        'w,
+        's,
+        (
+            Entity,
+            Ref<'static, RubyTranslation>,
+            &'static LinkedRubyTranslationText,
+            TextRootEntity,
+        ),
+    >,
+    stacks: Query<
+        'w,
+        's,
+        (
+            Entity,
+            Ref<'static, RubyStack>,
+            &'static LinkedRubyStackTexts,
+            TextRootEntity,
+        ),
+    >,
+    stack_texts: Query<'w, 's, &'static RubyStackText>,
+}
+
+impl OuterAnnotations<'_, '_> {
+    /// Base entity, whether its slot changed, the [`Ruby`], its annotation text, and the text root
+    /// of each annotation, in stacking order for each base.
+    fn iter(&self) -> impl Iterator<Item = (Entity, bool, &Ruby, Entity, Option<Entity>)> {
+        let translations =
+            self.translations
+                .iter()
+                .map(|(base, translation, linked, text_root)| {
+                    (
+                        base,
+                        translation.is_changed(),
+                        &translation.into_inner().0,
+                        linked.entity(),
+                        text_root.get(),
+                    )
+                });
+        let stacks = self
+            .stacks
+            .iter()
+            .flat_map(|(base, stack, linked, text_root)| {
+                let changed = stack.is_changed();
+                let stack = stack.into_inner();
+                let mut texts = linked
+                    .entities()
+                    .iter()
+                    .filter_map(|&rt_id| Some((self.stack_texts.get(rt_id).ok()?.index, rt_id)))
+                    .collect::<Vec<_>>();
+                texts.sort_unstable();
+                let text_root = text_root.get();
+                texts.into_iter().filter_map(move |(index, rt_id)| {
+                    Some((base, changed, stack.0.get(index)?, rt_id, text_root))
+                })
+            });
+        translations.chain(stacks)
+    }
+}
+
+/// Respawns the annotation texts of a [`RubyStack`] whose number of annotations changed, the same
+/// way as when it is added.
+fn resize_ruby_stacks(
+    stacks: Query<(Entity, &RubyStack, Option<&LinkedRubyStackTexts>), Changed<RubyStack>>,
+    mut respawned: Local<EntityHashMap<usize>>,
+    mut commands: Commands,
+) {
+    let last_respawned = core::mem::take(&mut *respawned);
+    for (base, stack, linked) in &stacks {
+        let len = stack.0.len();
+        let text_count = linked.map_or(0, |linked| linked.entities().len());
+        // Not every annotation may get its text (e.g. with `NoRuby` or past
+        // `FuriganaSettings::max_ruby_entities`), which respawning them again would not change
+        if text_count == len || last_respawned.get(&base) == Some(&len) {
+            continue;
+        }
+        // Despawned first, so that the stack is no longer linked when reinserted
+        commands
+            .entity(base)
+            .despawn_related::<LinkedRubyStackTexts>()
+            .remove::<RubyStack>()
+            .insert(stack.clone());
+        respawned.insert(base, len);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
 pub enum RubyPosition {
     /// Example:
     ///
@@ -78,9 +1106,24 @@ pub enum RubyPosition {
     ///
     /// <ruby style="ruby-position: under"><rb>Lorem ipsum</rb><rt>Ruby</rt></ruby>
     Under,
+    /// To the right of vertical (tate-gaki) base text, aligned along its column.
+    ///
+    /// Bevy has no vertical text layout, so vertical text is written one character per line, and
+    /// the ruby text is stacked the same way. [`Self::Over`] and [`Self::Under`] are for horizontal
+    /// text.
+    Right,
+    /// To the left of vertical base text. See [`Self::Right`].
+    Left,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+impl RubyPosition {
+    /// Whether the ruby is beside vertical base text, rather than over or under horizontal text.
+    pub const fn is_beside(self) -> bool {
+        matches!(self, Self::Right | Self::Left)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
 pub enum RubyAlign {
     /// Example:
     ///
@@ -95,6 +1138,260 @@ pub enum RubyAlign {
     ///
     /// <ruby style="ruby-align: end"><rb>Lorem ipsum</rb><rt>Ruby</rt></ruby>
     End,
+    /// Spreads the ruby characters evenly across the base text, with the first one flush left and
+    /// the last one flush right. A single character or a ruby wider than the base is centered.
+    ///
+    /// Example:
+    ///
+    /// <ruby style="ruby-align: space-between"><rb>Lorem ipsum</rb><rt>Ruby</rt></ruby>
+    Justify,
+    /// Spreads the ruby characters evenly across the base text with half the spacing between them
+    /// at each edge (1:2:1 distribution), e.g. two characters at 1/4 and 3/4 of the base. A single
+    /// character or a ruby wider than the base is centered.
+    ///
+    /// Example:
+    ///
+    /// <ruby style="ruby-align: space-around"><rb>Lorem ipsum</rb><rt>Ruby</rt></ruby>
+    Distribute,
+}
+
+impl RubyAlign {
+    /// Whether the glyphs of the ruby text are spread across the base text.
+    pub(crate) const fn spreads_glyphs(self) -> bool {
+        matches!(self, Self::Justify | Self::Distribute)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default, Reflect)]
+pub enum RubyMode {
+    /// Ruby text is placed over the whole base text, and may overhang its neighbors
+    /// when it is wider than the base.
+    #[default]
+    Group,
+    /// Mono-ruby: one reading per character of the base text, each centered over its own
+    /// character (e.g. `["とう", "きょう"]` for "東京"), and pushed along only as far as needed to
+    /// not overlap the reading before it. Empty readings (e.g. for okurigana) leave their
+    /// character without ruby. Set with [`Ruby::mono`], which also sets [`Ruby::rt`] to the
+    /// readings joined.
+    ///
+    /// Readings are placed as a group instead if the annotated line of the base doesn't have a
+    /// character per reading, beside vertical text, over merged spans, or with
+    /// [`Ruby::base_rect_override`]. Ruby isn't shrunk to fit its base here; use
+    /// [`RubyMaxWidth::MatchBase`] for that.
+    Mono(Vec<String>),
+}
+
+/// What [`RubyPosition::Over`] ruby text is placed against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
+pub enum RubyAnchor {
+    /// Top of the line box of the base text.
+    #[default]
+    LineBox,
+    /// Top of the capital letters of the base text, so that ruby over Latin text sits closer to it.
+    ///
+    /// Falls back to [`Self::LineBox`] if the font has no cap height metric, or with
+    /// [`Ruby::base_rect_override`].
+    CapHeight,
+}
+
+/// Where the ruby text box sits relative to the edge of the base text's line box, to fine-tune
+/// the gap between ruby and base across fonts.
+///
+/// Beside vertical text, this is relative to the side of the column.
+///
+/// Ignored with [`RubyAnchor::CapHeight`], which always places the box right above the capitals,
+/// and with [`RubyGapMode::Fixed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
+pub enum RubyVerticalAlign {
+    /// Centered on the edge.
+    #[default]
+    Center,
+    /// Just outside the edge, further from the base.
+    Outside,
+    /// Just inside the edge, closer to the base.
+    Inside,
+}
+
+/// How far a ruby text extends beyond its base text on each side, in logical pixels.
+///
+/// Kept up to date on every ruby (and translation) text entity once it is placed, so that layout
+/// code can leave room for the overhang, e.g. between adjacent annotated words.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RubyOverhang {
+    /// Overhang past the start (left edge) of the base text.
+    pub left: f32,
+    /// Overhang past the end (right edge) of the base text.
+    pub right: f32,
+}
+
+/// Which of the rects of a text section is annotated when it has several, such as when a span
+/// wraps onto multiple lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SectionRectTieBreak {
+    /// The topmost rect, i.e. the first line.
+    #[default]
+    Topmost,
+    /// The first rect reported by the text layout.
+    First,
+    /// The widest rect.
+    Widest,
+}
+
+/// How the gap between ruby text and its base text is determined.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Reflect)]
+pub enum RubyGapMode {
+    /// Follows [`Ruby::anchor`] and [`Ruby::vertical_align`].
+    #[default]
+    Auto,
+    /// Exactly this many logical pixels between the ruby text box and the cap height line of the
+    /// base text for [`RubyPosition::Over`], or its baseline for [`RubyPosition::Under`].
+    ///
+    /// Falls back to the edge of the line box if the font has no cap height metric or the text is
+    /// not laid out into glyphs, and to the edges of [`Ruby::base_rect_override`] if set.
+    Fixed(f32),
+}
+
+/// Unit of [`Ruby::offset`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
+pub enum RubyOffsetUnit {
+    /// Logical pixels.
+    #[default]
+    Pixels,
+    /// Multiples of the base text's font size, so that the offset stays proportional across sizes.
+    Em,
+}
+
+/// Width of the spaces between words of ruby text, to tighten multi-word (e.g. Latin) readings to
+/// fit the base text.
+///
+/// Spaces in [`Ruby::rt`] are displayed as narrower space characters, which the ruby font (or a
+/// fallback) must have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Reflect)]
+pub enum RubyWordSpacing {
+    /// Spaces as written.
+    #[default]
+    Normal,
+    /// Thin spaces (U+2009).
+    Thin,
+    /// Hair spaces (U+200A).
+    Hair,
+    /// Spaces removed.
+    None,
+}
+
+/// How to show ruby with an empty or whitespace-only [`Ruby::rt`]. See
+/// [`FuriganaSettings::blank_ruby`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BlankRuby {
+    /// Not shown nor placed, like ruby that isn't laid out.
+    #[default]
+    Skip,
+    /// Placed as a single thin space (U+2009), e.g. to keep a slot for a reading filled in later.
+    ThinSpace,
+}
+
+/// Color of ruby text looked up at runtime. See [`Ruby::color_ref`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum RubyColorRef {
+    /// Color at this index of [`RubyPalette`].
+    Palette(usize),
+}
+
+/// Colors referenced by [`RubyColorRef::Palette`]. Editing it recolors all ruby referencing it.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RubyPalette(pub Vec<Color>);
+
+/// Width limit for ruby text. See [`Ruby::max_width`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Reflect)]
+pub enum RubyMaxWidth {
+    /// No limit.
+    #[default]
+    None,
+    /// As wide as the base text.
+    MatchBase,
+    /// Width in logical pixels.
+    Px(f32),
+}
+
+/// Drop shadow of ruby text, rendered with `TextShadow` for UI and `Text2dShadow` for `Text2d`.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct RubyShadow {
+    /// Displacement in logical pixels, with Y pointing down for both UI and `Text2d`.
+    pub offset: Vec2,
+    pub color: Color,
+}
+
+impl Default for RubyShadow {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::splat(1.0),
+            color: Color::BLACK,
+        }
+    }
+}
+
+/// Outline of ruby text.
+///
+/// Bevy text has no outlines, so it is drawn as copies of the ruby text in `color` around it.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct RubyOutline {
+    pub color: Color,
+    /// Width relative to the font size of the ruby text, so that it scales with
+    /// [`Ruby::font_size_scale`].
+    pub width: f32,
+}
+
+impl Default for RubyOutline {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            width: 0.08,
+        }
+    }
+}
+
+impl RubyOutline {
+    /// Directions of the copies of the ruby text that make up the outline, with Y pointing down.
+    const DIRECTIONS: [Vec2; 8] = [
+        Vec2::new(1.0, 0.0),
+        Vec2::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        Vec2::new(0.0, 1.0),
+        Vec2::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        Vec2::new(-1.0, 0.0),
+        Vec2::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+        Vec2::new(0.0, -1.0),
+        Vec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    ];
+
+    /// Offset in logical pixels of the copy in `direction` for ruby text of `font_size`.
+    fn offset(&self, direction: Vec2, font_size: f32) -> Vec2 {
+        direction * self.width * font_size
+    }
+
+    /// Color of the copies, fading along with the ruby text of `text_color`.
+    fn text_color(&self, text_color: TextColor) -> TextColor {
+        TextColor(
+            self.color
+                .with_alpha(self.color.alpha() * text_color.0.alpha()),
+        )
+    }
+}
+
+/// Tracks the fade-in of a newly spawned ruby text.
+#[derive(Component, Default)]
+struct RubyFadeIn {
+    /// Elapsed time when the ruby text was first updated.
+    start: Option<Duration>,
+}
+
+impl RubyFadeIn {
+    /// Returns the alpha multiplier for the current time, or `None` once the fade-in is complete.
+    fn alpha(&mut self, now: Duration, duration: Option<Duration>) -> Option<f32> {
+        let duration = duration.filter(|d| !d.is_zero())?;
+        let start = *self.start.get_or_insert(now);
+        let t = (now - start).as_secs_f32() / duration.as_secs_f32();
+        (t < 1.0).then_some(t)
+    }
 }
 
 #[derive(QueryData)]
@@ -113,3 +1410,320 @@ impl<'w, 's> TextRootEntityItem<'w, 's> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "text2d")]
+    #[test]
+    fn test_ui_and_2d_ruby_in_same_app() {
+        let mut app = App::new();
+        app.add_plugins(FuriganaPlugin);
+
+        let ui_entity = app
+            .world_mut()
+            .spawn((Ruby::new("ui"), Text::new("text")))
+            .id();
+        let text_2d_entity = app
+            .world_mut()
+            .spawn((Ruby::new("2d"), Text2d::new("text")))
+            .id();
+
+        let world = app.world_mut();
+
+        let linked = world.get::<LinkedRubyText>(ui_entity).unwrap();
+        assert_eq!(world.get::<Text>(linked.entity()).unwrap().0, "ui");
+        assert!(world.get::<LinkedRubyText2d>(ui_entity).is_none());
+
+        let linked = world.get::<LinkedRubyText2d>(text_2d_entity).unwrap();
+        assert_eq!(world.get::<Text2d>(linked.entity()).unwrap().0, "2d");
+        assert!(world.get::<LinkedRubyText>(text_2d_entity).is_none());
+
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 1);
+        assert_eq!(world.query::<&RubyText2d>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn test_ruby_text_color_auto_contrast() {
+        let mut settings = FuriganaSettings::default();
+        let gray = TextColor(Color::srgb(0.5, 0.5, 0.5));
+        let dark = TextBackgroundColor(Color::srgb(0.1, 0.1, 0.1));
+        let ruby = Ruby::new("ruby");
+
+        // Disabled by default
+        assert_eq!(
+            ruby_text_color(&ruby, gray, Some(&dark), &default(), &settings),
+            gray
+        );
+
+        settings.ruby_auto_contrast = true;
+        assert_eq!(
+            ruby_text_color(&ruby, gray, Some(&dark), &default(), &settings),
+            TextColor(Color::WHITE)
+        );
+        // No background to contrast against
+        assert_eq!(
+            ruby_text_color(&ruby, gray, None, &default(), &settings),
+            gray
+        );
+
+        settings.ruby_contrast_background = Some(Color::srgb(0.9, 0.9, 0.9));
+        assert_eq!(
+            ruby_text_color(&ruby, gray, None, &default(), &settings),
+            TextColor(Color::BLACK)
+        );
+
+        // Readable colors are kept
+        let white = TextColor(Color::WHITE);
+        assert_eq!(
+            ruby_text_color(&ruby, white, Some(&dark), &default(), &settings),
+            white
+        );
+    }
+
+    #[test]
+    fn test_emoji_ruby_text_color() {
+        let settings = FuriganaSettings::default();
+        let red = TextColor(Color::srgba(1.0, 0.0, 0.0, 0.5));
+
+        // Emoji keep their own colors, but fade with the base text
+        let emoji = Ruby::new("👍🏽 🇯🇵");
+        assert_eq!(
+            ruby_text_color(&emoji, red, None, &default(), &settings),
+            TextColor(Color::WHITE.with_alpha(0.5))
+        );
+        assert_eq!(
+            ruby_text_color(&Ruby::new("☺\u{FE0F}"), red, None, &default(), &settings),
+            TextColor(Color::WHITE.with_alpha(0.5))
+        );
+
+        // Mixed with text, or with an explicit color
+        assert_eq!(
+            ruby_text_color(&Ruby::new("いいね👍"), red, None, &default(), &settings),
+            red
+        );
+        let blue = TextColor(Color::srgb(0.0, 0.0, 1.0));
+        let colored = Ruby {
+            color: Some(blue),
+            ..emoji
+        };
+        assert_eq!(
+            ruby_text_color(&colored, red, None, &default(), &settings),
+            TextColor(Color::srgba(0.0, 0.0, 1.0, 0.5))
+        );
+    }
+
+    #[test]
+    fn test_ruby_builder() {
+        let ruby = Ruby::new("かんじ")
+            .under()
+            .align(RubyAlign::End)
+            .scale(0.8)
+            .color(Color::WHITE)
+            .offset(Vec2::new(1.0, 2.0));
+        assert_eq!(
+            ruby,
+            Ruby {
+                position: RubyPosition::Under,
+                align: RubyAlign::End,
+                font_size_scale: 0.8,
+                color: Some(TextColor(Color::WHITE)),
+                offset: Vec2::new(1.0, 2.0),
+                ..Ruby::new("かんじ")
+            }
+        );
+        assert_eq!(ruby.over().position, RubyPosition::Over);
+    }
+
+    #[test]
+    fn test_non_cjk_sections() {
+        let mut settings = FuriganaSettings::default();
+        let mut world = World::new();
+        let sections = [
+            "漢字",
+            "かな",
+            "한글",
+            "Lorem ipsum",
+            "「、」",
+            "Tokyo 東京",
+        ]
+        .map(|text| (world.spawn_empty().id(), text));
+        assert!(non_cjk_sections(sections.into_iter(), &settings).is_empty());
+
+        settings.cjk_only = true;
+        let non_cjk = non_cjk_sections(sections.into_iter(), &settings);
+        assert_eq!(
+            non_cjk,
+            [sections[3].0, sections[4].0].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_ruby_font_size_multiplier() {
+        let text_font = TextFont::from_font_size(20.0);
+        let mut settings = FuriganaSettings::default();
+        let font_size = |font_size_scale, settings: &FuriganaSettings| {
+            let ruby = Ruby {
+                font_size_scale,
+                ..default()
+            };
+            ruby_text_font(&ruby, &text_font, settings).font_size
+        };
+        assert_eq!(font_size(0.5, &settings), 10.0);
+        assert_eq!(font_size(0.25, &settings), 5.0);
+
+        settings.ruby_font_size_multiplier = 1.5;
+        assert_eq!(font_size(0.5, &settings), 15.0);
+        assert_eq!(font_size(0.25, &settings), 7.5);
+
+        settings.round_ruby_font_size = true;
+        assert_eq!(font_size(0.25, &settings), 8.0);
+    }
+
+    #[test]
+    fn test_ruby_text_font_inherits_base_style() {
+        let text_font = TextFont {
+            font_smoothing: bevy::text::FontSmoothing::None,
+            line_height: LineHeight::Px(40.0),
+            ..default()
+        };
+        let ruby_font = ruby_text_font(&Ruby::default(), &text_font, &default());
+        assert_eq!(ruby_font.font_smoothing, text_font.font_smoothing);
+        assert_eq!(ruby_font.line_height, LineHeight::default());
+    }
+
+    #[test]
+    fn test_max_ruby_entities() {
+        let mut app = App::new();
+        app.add_plugins(FuriganaPlugin)
+            .insert_resource(FuriganaSettings {
+                max_ruby_entities: Some(2),
+                ..default()
+            });
+
+        let world = app.world_mut();
+        let texts = (0..3)
+            .map(|_| world.spawn((Text::new("text"), Ruby::new("ruby"))).id())
+            .collect::<Vec<_>>();
+
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 2);
+        assert!(world.get::<LinkedRubyText>(texts[2]).is_none());
+        // Ruby data is kept
+        assert!(world.get::<Ruby>(texts[2]).is_some());
+    }
+
+    #[test]
+    fn test_ruby_scene_round_trip() {
+        use bevy::scene::{DynamicSceneBuilder, ron, serde::SceneSerializer};
+
+        let new_app = || {
+            let mut app = App::new();
+            app.add_plugins(FuriganaPlugin).register_type::<Text>();
+            app
+        };
+        let ruby = Ruby {
+            position: RubyPosition::Under,
+            align: RubyAlign::Start,
+            color: Some(TextColor(Color::WHITE)),
+            ..Ruby::new("かんじ")
+        };
+
+        let mut app = new_app();
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text::new("漢字"), ruby.clone())).id();
+        let scene = DynamicSceneBuilder::from_world(world)
+            .deny_all_components()
+            .allow_component::<Text>()
+            .allow_component::<Ruby>()
+            .extract_entity(text_entity)
+            .build();
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let ron = ron::to_string(&SceneSerializer::new(&scene, &registry.read())).unwrap();
+        assert!(ron.contains("かんじ"));
+
+        let mut app = new_app();
+        let world = app.world_mut();
+        let mut entity_map = default();
+        scene.write_to_world(world, &mut entity_map).unwrap();
+        world.flush();
+
+        let text_entity = entity_map[&text_entity];
+        assert_eq!(world.get::<Ruby>(text_entity), Some(&ruby));
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        assert_eq!(world.get::<Text>(rt_id).unwrap().0, "かんじ");
+    }
+
+    #[test]
+    fn test_ruby_text_layout_independent_of_base() {
+        let mut app = App::new();
+        app.add_plugins(FuriganaPlugin);
+
+        let world = app.world_mut();
+        let layout = TextLayout::new_with_justify(Justify::Center);
+        let ui_entity = world.spawn((Text::new("ui"), layout, Ruby::new("ui"))).id();
+        let linked = world.get::<LinkedRubyText>(ui_entity).unwrap();
+        assert_eq!(
+            world.get::<TextLayout>(linked.entity()).unwrap().justify,
+            Justify::default()
+        );
+
+        #[cfg(feature = "text2d")]
+        {
+            let text_2d_entity = world
+                .spawn((Text2d::new("2d"), layout, Ruby::new("2d")))
+                .id();
+            let linked = world.get::<LinkedRubyText2d>(text_2d_entity).unwrap();
+            assert_eq!(
+                world.get::<TextLayout>(linked.entity()).unwrap().justify,
+                Justify::default()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ruby_word_spacing() {
+        let mut app = App::new();
+        app.add_plugins(FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("lorem"),
+                Ruby {
+                    word_spacing: RubyWordSpacing::Thin,
+                    ..Ruby::new("consectetur adipiscing elit")
+                },
+            ))
+            .id();
+        let linked = world.get::<LinkedRubyText>(text_entity).unwrap();
+        assert_eq!(
+            world.get::<Text>(linked.entity()).unwrap().0,
+            "consectetur\u{2009}adipiscing\u{2009}elit"
+        );
+
+        let ruby = Ruby {
+            word_spacing: RubyWordSpacing::None,
+            ..Ruby::new("a b")
+        };
+        assert_eq!(ruby.display_text(BlankRuby::Skip), "ab");
+        assert_eq!(Ruby::new("a b").display_text(BlankRuby::Skip), "a b");
+        // Stacked beside vertical text
+        let ruby = Ruby {
+            position: RubyPosition::Right,
+            ..Ruby::new("かん じ")
+        };
+        assert_eq!(ruby.display_text(BlankRuby::Skip), "か\nん\nじ");
+        // Whitespace only
+        let ruby = Ruby::new("  ");
+        assert_eq!(ruby.display_text(BlankRuby::Skip), "");
+        assert_eq!(ruby.display_text(BlankRuby::ThinSpace), "\u{2009}");
+        // Reversed, keeping combining marks with their base character
+        let ruby = Ruby {
+            reversed: true,
+            ..Ruby::new("か\u{3099}んじ")
+        };
+        assert_eq!(ruby.display_text(BlankRuby::Skip), "じんか\u{3099}");
+        assert_eq!(ruby.rt, "か\u{3099}んじ");
+    }
+}
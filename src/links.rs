@@ -0,0 +1,136 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+#[cfg(feature = "text2d")]
+use crate::LinkedRubyText2d;
+use crate::{LinkedRubyStackTexts, LinkedRubyText, LinkedRubyTranslationText, RubyStackText};
+
+/// [`SystemParam`] to look up the ruby text of base entities, for both UI and 2D text.
+#[derive(SystemParam)]
+pub struct RubyLinks<'w, 's> {
+    ui: Query<'w, 's, &'static LinkedRubyText>,
+    #[cfg(feature = "text2d")]
+    text_2d: Query<'w, 's, &'static LinkedRubyText2d>,
+    translations: Query<'w, 's, &'static LinkedRubyTranslationText>,
+    stacks: Query<'w, 's, &'static LinkedRubyStackTexts>,
+    stack_texts: Query<'w, 's, &'static RubyStackText>,
+}
+
+impl RubyLinks<'_, '_> {
+    /// Ruby text entity of `base`, if it has a [`Ruby`](crate::Ruby) with its ruby text spawned.
+    pub fn ruby_text(&self, base: Entity) -> Option<Entity> {
+        let ruby_text = self.ui.get(base).ok().map(LinkedRubyText::entity);
+
+        #[cfg(feature = "text2d")]
+        let ruby_text =
+            ruby_text.or_else(|| self.text_2d.get(base).ok().map(LinkedRubyText2d::entity));
+
+        ruby_text
+    }
+
+    /// Translation text entity of `base`, if it has a [`RubyTranslation`](crate::RubyTranslation)
+    /// with its translation text spawned.
+    pub fn translation_text(&self, base: Entity) -> Option<Entity> {
+        self.translations
+            .get(base)
+            .ok()
+            .map(LinkedRubyTranslationText::entity)
+    }
+
+    /// Annotation text entity of the `index`-th annotation of the [`RubyStack`](crate::RubyStack)
+    /// of `base`, if spawned.
+    pub fn stack_text(&self, base: Entity, index: usize) -> Option<Entity> {
+        self.stacks
+            .get(base)
+            .ok()?
+            .entities()
+            .iter()
+            .copied()
+            .find(|&rt_id| {
+                self.stack_texts
+                    .get(rt_id)
+                    .is_ok_and(|stack_text| stack_text.index == index)
+            })
+    }
+
+    /// Whether `base` has ruby, translation or stacked annotation text.
+    pub fn is_annotated(&self, base: Entity) -> bool {
+        self.ruby_text(base).is_some()
+            || self.translation_text(base).is_some()
+            || self
+                .stacks
+                .get(base)
+                .is_ok_and(|stack| !stack.entities().is_empty())
+    }
+}
+
+/// Ruby text entity of `base`, like [`RubyLinks::ruby_text`] but with direct [`World`] access.
+pub fn ruby_text_entity(world: &World, base: Entity) -> Option<Entity> {
+    let ruby_text = world
+        .get::<LinkedRubyText>(base)
+        .map(LinkedRubyText::entity);
+
+    #[cfg(feature = "text2d")]
+    let ruby_text = ruby_text.or_else(|| {
+        world
+            .get::<LinkedRubyText2d>(base)
+            .map(LinkedRubyText2d::entity)
+    });
+
+    ruby_text
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{Ruby, RubyTranslation};
+
+    #[test]
+    fn test_ruby_links() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let ui_base = world
+            .spawn((
+                Text::new("犬"),
+                Ruby::new("いぬ"),
+                RubyTranslation::new("dog"),
+            ))
+            .id();
+        let plain = world.spawn(Text::new("猫")).id();
+
+        let ruby_text = ruby_text_entity(world, ui_base).unwrap();
+        assert!(world.get::<crate::RubyText>(ruby_text).is_some());
+        assert_eq!(ruby_text_entity(world, plain), None);
+
+        let links = world
+            .run_system_once(move |links: RubyLinks| {
+                (
+                    links.ruby_text(ui_base),
+                    links.translation_text(ui_base).is_some(),
+                    links.is_annotated(plain),
+                )
+            })
+            .unwrap();
+        assert_eq!(links, (Some(ruby_text), true, false));
+    }
+
+    #[cfg(feature = "text2d")]
+    #[test]
+    fn test_ruby_links_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let base = world.spawn((Text2d::new("犬"), Ruby::new("いぬ"))).id();
+
+        let ruby_text = ruby_text_entity(world, base).unwrap();
+        assert!(world.get::<crate::RubyText2d>(ruby_text).is_some());
+        let linked = world
+            .run_system_once(move |links: RubyLinks| links.ruby_text(base))
+            .unwrap();
+        assert_eq!(linked, Some(ruby_text));
+    }
+}
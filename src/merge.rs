@@ -0,0 +1,105 @@
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+
+use crate::{
+    BlankRuby, NoRuby, Ruby, SectionRectTieBreak,
+    layout::{TextBlock, base_rect},
+};
+
+/// Relationship from a single-character `TextSpan` to the span whose ruby text shows its [`Ruby`]
+/// too. See [`FuriganaSettings::merge_adjacent_ruby`](crate::FuriganaSettings::merge_adjacent_ruby).
+#[derive(Component, Clone, Copy, Debug)]
+#[relationship(relationship_target = MergedRuby)]
+pub struct RubyMergedInto(pub Entity);
+
+/// Spans following this one whose [`Ruby`] is shown by the ruby text of this span, in text order.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = RubyMergedInto)]
+pub struct MergedRuby(Vec<Entity>);
+
+impl MergedRuby {
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+/// Span data compared to decide whether adjacent ruby can be merged.
+pub(crate) type MergeableSpan<'a> = (&'a TextSpan, &'a Ruby, &'a TextFont, &'a TextColor);
+
+/// Finds the span whose ruby text should show the ruby of `span` as well: the first of the run of
+/// single-character spans with identically styled [`Ruby`] right before and including `span`.
+///
+/// Returns `None` if `span` starts the run (or is not part of one).
+pub(crate) fn merge_leader(
+    span: Entity,
+    siblings: &[Entity],
+    spans: &Query<MergeableSpan, Without<NoRuby>>,
+) -> Option<Entity> {
+    let index = siblings.iter().position(|&sibling| sibling == span)?;
+    let mut leader = None;
+    for pair in siblings[..=index].windows(2).rev() {
+        let (Ok(prev), Ok(next)) = (spans.get(pair[0]), spans.get(pair[1])) else {
+            break;
+        };
+        if !mergeable(prev, next) {
+            break;
+        }
+        leader = Some(pair[0]);
+    }
+    leader
+}
+
+fn mergeable(
+    (a_span, a_ruby, a_font, a_color): MergeableSpan,
+    (b_span, b_ruby, b_font, b_color): MergeableSpan,
+) -> bool {
+    let single_char = |span: &TextSpan| span.chars().count() == 1;
+    let style = |ruby: &Ruby| Ruby {
+        rt: String::new(),
+        ..ruby.clone()
+    };
+    single_char(a_span)
+        && single_char(b_span)
+        && a_font == b_font
+        && a_color == b_color
+        && style(a_ruby) == style(b_ruby)
+}
+
+/// Ruby text of `base`, followed by that of the spans merged into it.
+pub(crate) fn merged_display_text<'a>(
+    ruby: &'a Ruby,
+    merged: Option<&MergedRuby>,
+    rubies: &Query<&Ruby>,
+    blank: BlankRuby,
+) -> Cow<'a, str> {
+    let Some(merged) = merged else {
+        return ruby.display_text(blank);
+    };
+    let mut text = ruby.display_text(blank).into_owned();
+    for ruby in rubies.iter_many(merged.entities()) {
+        text.push_str(&ruby.display_text(blank));
+    }
+    Cow::Owned(text)
+}
+
+/// `ruby` of `base`, overriding its base rect to span the spans `merged` into it as well.
+pub(crate) fn merged_base_ruby<'a>(
+    ruby: &'a Ruby,
+    base: Entity,
+    merged: Option<&MergedRuby>,
+    block: &TextBlock,
+    tie_break: SectionRectTieBreak,
+) -> Cow<'a, Ruby> {
+    let Some(merged) = merged.filter(|_| ruby.base_rect_override.is_none()) else {
+        return Cow::Borrowed(ruby);
+    };
+    let base_rect_override = core::iter::once(base)
+        .chain(merged.entities().iter().copied())
+        .filter_map(|entity| base_rect(block, entity, tie_break, ruby.position))
+        .reduce(|a, b| a.union(b));
+    Cow::Owned(Ruby {
+        base_rect_override,
+        ..ruby.clone()
+    })
+}
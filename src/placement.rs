@@ -0,0 +1,427 @@
+use core::fmt::Write;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*, text::TextLayoutInfo};
+
+use crate::{
+    FuriganaSettings, Ruby, RubyTranslation,
+    layout::{RubyBox, TextBlock, place_ruby},
+};
+
+/// Sent when a ruby text entity is spawned for a base, e.g. to attach animation components to it.
+///
+/// The ruby text is hidden until placed; see [`RubyPositioned`].
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RubyTextSpawned {
+    /// Entity with [`Ruby`] (or [`RubyTranslation`](crate::RubyTranslation),
+    /// [`RubyStack`](crate::RubyStack)).
+    pub base: Entity,
+    /// Ruby (or translation, stacked annotation) text entity.
+    pub ruby_text: Entity,
+}
+
+/// Sent the first frame a ruby text is placed against its base and shown, e.g. to start a reveal
+/// animation.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RubyPositioned {
+    /// Entity with [`Ruby`] (or [`RubyTranslation`](crate::RubyTranslation),
+    /// [`RubyStack`](crate::RubyStack)).
+    pub base: Entity,
+    /// Ruby (or translation, stacked annotation) text entity.
+    pub ruby_text: Entity,
+}
+
+/// Sent the first frame a newly spawned ruby text stays where it was placed in the previous frame,
+/// e.g. to start an animation once the ruby has settled.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RubyStabilized {
+    /// Entity with [`Ruby`] (or [`RubyTranslation`](crate::RubyTranslation),
+    /// [`RubyStack`](crate::RubyStack)).
+    pub base: Entity,
+    /// Ruby (or translation, stacked annotation) text entity.
+    pub ruby: Entity,
+}
+
+/// Tracks the placement of a ruby text until it settles, for [`RubyPositioned`] and
+/// [`RubyStabilized`].
+#[derive(Component, Default)]
+pub(crate) struct RubySettling {
+    /// Position and size placed in the previous frame.
+    last: Option<(Vec3, Vec2)>,
+    positioned: bool,
+    stabilized: bool,
+}
+
+impl RubySettling {
+    /// Records whether the ruby text is placed this frame, and returns whether it has just been
+    /// placed for the first time.
+    pub(crate) fn position(&mut self, placed: bool) -> bool {
+        let first = placed && !self.positioned;
+        self.positioned |= placed;
+        first
+    }
+
+    /// Records `placement` of this frame (`None` if not placed), and returns whether the ruby text
+    /// has just settled.
+    pub(crate) fn settle(&mut self, placement: Option<(Vec3, Vec2)>) -> bool {
+        if self.stabilized {
+            return false;
+        }
+        self.stabilized = placement.is_some() && placement == self.last;
+        self.last = placement;
+        self.stabilized
+    }
+}
+
+/// Placement of a ruby text against its base text, as returned by [`compute_placement_now`] or
+/// [`RubyPlacementProvider::place_ruby`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RubyPlacement {
+    /// Bounds of the base text, in logical pixels relative to the top-left of the text block
+    /// (Y+ down).
+    pub base_rect: Rect,
+    /// Bounds of the ruby text, in the same space as `base_rect`.
+    pub ruby_rect: Rect,
+    /// Center of the ruby text. For UI text, this is in the space of `UiGlobalTransform`
+    /// (physical pixels); for `Text2d`, in world space; for a [`RubyPlacementProvider`], in the
+    /// same space as `base_rect`.
+    pub ruby_center: Vec3,
+}
+
+impl RubyPlacement {
+    /// Creates a placement from `base_rect` and `ruby_box` in physical pixels.
+    pub(crate) fn new(
+        base_rect: Rect,
+        ruby_box: RubyBox,
+        scale_factor: f32,
+        ruby_center: Vec3,
+    ) -> Self {
+        Self {
+            base_rect: Rect::from_corners(
+                base_rect.min / scale_factor,
+                base_rect.max / scale_factor,
+            ),
+            ruby_rect: ruby_box.to_logical(scale_factor).rect(),
+            ruby_center,
+        }
+    }
+}
+
+/// Base text laid out by a custom text renderer (e.g. one drawing bitmap font sprites), to place
+/// ruby text for it with the same logic as for Bevy text.
+///
+/// Everything is in logical pixels relative to the top-left of the custom text block (Y+ down).
+pub trait RubyPlacementProvider {
+    /// Bounds of the base text to annotate.
+    fn base_rect(&self) -> Rect;
+
+    /// Size of the ruby text as laid out by the custom renderer.
+    fn ruby_size(&self) -> Vec2;
+
+    /// Font size of the base text, which [`RubyOffsetUnit::Em`](crate::RubyOffsetUnit::Em)
+    /// offsets are relative to.
+    fn base_font_size(&self) -> f32;
+
+    /// Places ruby text for `ruby`, whose `rt` the custom renderer draws centered at
+    /// [`RubyPlacement::ruby_center`] (in the same space as [`Self::base_rect`]), scaled to fit
+    /// [`RubyPlacement::ruby_rect`].
+    ///
+    /// Returns `None` if the ruby is not to be shown (e.g. a blank reading).
+    fn place_ruby(&self, ruby: &Ruby, settings: &FuriganaSettings) -> Option<RubyPlacement> {
+        let base_rect = self.base_rect();
+        let ruby = Ruby {
+            base_rect_override: Some(base_rect),
+            ..ruby.clone()
+        };
+        let layout_info = TextLayoutInfo {
+            scale_factor: 1.0,
+            ..default()
+        };
+        let ruby_box = place_ruby(
+            &ruby,
+            &TextBlock::new(&layout_info, &[]),
+            Entity::PLACEHOLDER,
+            settings,
+            self.ruby_size(),
+            None,
+            None,
+            self.base_font_size(),
+        )?;
+        Some(RubyPlacement::new(
+            base_rect,
+            ruby_box,
+            1.0,
+            ruby_box.center.extend(0.0),
+        ))
+    }
+}
+
+/// Places ruby text of `ruby_size` for `ruby` on the text section of `base` in `layout_info`, without
+/// a running app, e.g. to test placement against synthetic layout from [`section_layout_info`].
+///
+/// `ruby_size` and `base_font_size` are in logical pixels. The returned
+/// [`RubyPlacement::ruby_center`] is in the same space as [`RubyPlacement::base_rect`].
+/// Font metrics are not available here, so placement is relative to the line box.
+///
+/// Returns `None` if the section is not laid out, or the ruby is not to be shown.
+pub fn place_ruby_in_layout(
+    ruby: &Ruby,
+    layout_info: &TextLayoutInfo,
+    base: Entity,
+    ruby_size: Vec2,
+    base_font_size: f32,
+    settings: &FuriganaSettings,
+) -> Option<RubyPlacement> {
+    let scale_factor = layout_info.scale_factor;
+    let ruby_box = place_ruby(
+        ruby,
+        &TextBlock::new(layout_info, &[]),
+        base,
+        settings,
+        ruby_size * scale_factor,
+        None,
+        None,
+        base_font_size * scale_factor,
+    )?;
+    Some(RubyPlacement::new(
+        ruby_box.base_rect,
+        ruby_box,
+        scale_factor,
+        ruby_box.to_logical(scale_factor).center.extend(0.0),
+    ))
+}
+
+/// Creates a `TextLayoutInfo` with `section_rects` (in physical pixels) and no glyphs, sized to
+/// contain all of them, for [`place_ruby_in_layout`].
+pub fn section_layout_info(
+    section_rects: impl IntoIterator<Item = (Entity, Rect)>,
+    scale_factor: f32,
+) -> TextLayoutInfo {
+    let section_rects = section_rects.into_iter().collect::<Vec<_>>();
+    let bounds = section_rects
+        .iter()
+        .fold(Rect::default(), |bounds, &(_, rect)| bounds.union(rect));
+    TextLayoutInfo {
+        scale_factor,
+        glyphs: vec![],
+        section_rects,
+        size: bounds.max,
+    }
+}
+
+/// Computes the placement of the ruby text of `base` (an entity with [`Ruby`](crate::Ruby)) right
+/// away, e.g. for a tooltip spawned the same frame as its text, rather than waiting for the ruby
+/// text to be placed in `PostUpdate`.
+///
+/// This forces layout first: Bevy's text layout systems (and, for UI text, its UI layout system)
+/// are run on `world`, so text and ruby spawned or edited this frame are placed as they will be
+/// shown.
+///
+/// Returns `None` if `base` has no ruby text, its text cannot be laid out yet (e.g. the font is
+/// still loading), or the ruby is not shown (e.g. with
+/// [`FuriganaDisabled`](crate::FuriganaDisabled)).
+pub fn compute_placement_now(world: &mut World, base: Entity) -> Option<RubyPlacement> {
+    crate::ui::lay_out_ui(world);
+    #[cfg(feature = "text2d")]
+    crate::text2d::lay_out_text_2d(world);
+
+    let placement = world
+        .run_system_once_with(crate::ui::ruby_placement, base)
+        .ok()
+        .flatten();
+
+    #[cfg(feature = "text2d")]
+    let placement = placement.or_else(|| {
+        world
+            .run_system_once_with(crate::text2d::ruby_placement_2d, base)
+            .ok()
+            .flatten()
+    });
+
+    placement
+}
+
+/// Returns the bounds of the ruby text of `base` (an entity with [`Ruby`](crate::Ruby)) in logical
+/// window coordinates (Y+ down from the top-left of the window), e.g. to put an overlay over it.
+///
+/// This is the text of the reading; see [`translation_window_rect`] for that of a
+/// [`RubyTranslation`](crate::RubyTranslation) of the same base.
+///
+/// This is where the ruby text was last placed, so it is `None` until the ruby text is placed
+/// and visible. For `Text2d`, the ruby text is projected with the active camera of the highest
+/// order.
+pub fn ruby_window_rect(world: &mut World, base: Entity) -> Option<Rect> {
+    let rect = world
+        .run_system_once_with(crate::ui::ruby_window_rect::<Ruby>, base)
+        .ok()
+        .flatten();
+
+    #[cfg(feature = "text2d")]
+    let rect = rect.or_else(|| {
+        world
+            .run_system_once_with(crate::text2d::ruby_window_rect_2d::<Ruby>, base)
+            .ok()
+            .flatten()
+    });
+
+    rect
+}
+
+/// Returns the bounds of the translation text of `base` (an entity with
+/// [`RubyTranslation`](crate::RubyTranslation)) in logical window coordinates, the same way as
+/// [`ruby_window_rect`] does for its reading.
+pub fn translation_window_rect(world: &mut World, base: Entity) -> Option<Rect> {
+    let rect = world
+        .run_system_once_with(crate::ui::ruby_window_rect::<RubyTranslation>, base)
+        .ok()
+        .flatten();
+
+    #[cfg(feature = "text2d")]
+    let rect = rect.or_else(|| {
+        world
+            .run_system_once_with(crate::text2d::ruby_window_rect_2d::<RubyTranslation>, base)
+            .ok()
+            .flatten()
+    });
+
+    rect
+}
+
+/// Describes how the ruby text of `base` is placed: the [`Ruby`] settings involved and the
+/// resulting [`RubyPlacement`] from [`compute_placement_now`], for debugging and bug reports.
+pub fn debug_placement(world: &mut World, base: Entity) -> String {
+    let Some(ruby) = world.get::<Ruby>(base).cloned() else {
+        return format!("{base} has no Ruby");
+    };
+
+    let mut out = format!("Ruby {:?} on {base}\n", ruby.rt);
+    let _ = writeln!(
+        out,
+        "  position: {:?}, align: {:?}, mode: {:?}",
+        ruby.position, ruby.align, ruby.mode
+    );
+    let _ = writeln!(
+        out,
+        "  anchor: {:?}, vertical_align: {:?}, gap: {:?}",
+        ruby.anchor, ruby.vertical_align, ruby.gap
+    );
+    let _ = writeln!(out, "  font_size_scale: {}", ruby.font_size_scale);
+    match ruby.base_rect_override {
+        Some(rect) => {
+            let _ = writeln!(out, "  base rect: overridden with {rect:?}");
+        }
+        None => {
+            let tie_break = world
+                .get_resource::<FuriganaSettings>()
+                .map(|settings| settings.section_rect_tie_break);
+            let _ = writeln!(out, "  base rect: from section rects ({tie_break:?})");
+        }
+    }
+
+    match compute_placement_now(world, base) {
+        Some(placement) => {
+            let _ = writeln!(out, "  placed base rect: {:?}", placement.base_rect);
+            let _ = writeln!(out, "  placed ruby rect: {:?}", placement.ruby_rect);
+            let _ = write!(out, "  ruby center: {:?}", placement.ruby_center);
+        }
+        None => {
+            out.push_str("  not placed: no ruby text, or the text is not laid out yet");
+        }
+    }
+    out
+}
+
+/// Bounding rect of `points`.
+pub(crate) fn bounding_rect(points: impl IntoIterator<Item = Vec2>) -> Rect {
+    points
+        .into_iter()
+        .fold(Rect::EMPTY, |rect, point| rect.union_point(point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RubyAlign, RubyOffsetUnit};
+
+    struct BitmapText {
+        base_rect: Rect,
+    }
+
+    impl RubyPlacementProvider for BitmapText {
+        fn base_rect(&self) -> Rect {
+            self.base_rect
+        }
+
+        fn ruby_size(&self) -> Vec2 {
+            Vec2::new(30.0, 10.0)
+        }
+
+        fn base_font_size(&self) -> f32 {
+            20.0
+        }
+    }
+
+    #[test]
+    fn test_place_ruby_in_layout() {
+        let mut world = World::new();
+        let [base, other, missing] = [(); 3].map(|_| world.spawn_empty().id());
+        let layout_info = section_layout_info(
+            [
+                (other, Rect::new(0.0, 0.0, 40.0, 40.0)),
+                (base, Rect::new(40.0, 0.0, 120.0, 40.0)),
+            ],
+            2.0,
+        );
+        assert_eq!(layout_info.size, Vec2::new(120.0, 40.0));
+
+        let settings = FuriganaSettings::default();
+        let placement = place_ruby_in_layout(
+            &Ruby::new("かんじ"),
+            &layout_info,
+            base,
+            Vec2::new(30.0, 10.0),
+            20.0,
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(placement.base_rect, Rect::new(20.0, 0.0, 60.0, 20.0));
+        assert_eq!(placement.ruby_center, Vec3::new(40.0, 0.0, 0.0));
+        assert_eq!(placement.ruby_rect, Rect::new(25.0, -5.0, 55.0, 5.0));
+
+        assert!(
+            place_ruby_in_layout(
+                &Ruby::new("かんじ"),
+                &layout_info,
+                missing,
+                Vec2::new(30.0, 10.0),
+                20.0,
+                &settings,
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_ruby_placement_provider() {
+        let text = BitmapText {
+            base_rect: Rect::new(10.0, 20.0, 50.0, 40.0),
+        };
+        let ruby = Ruby {
+            align: RubyAlign::Start,
+            offset: Vec2::new(0.0, 0.5),
+            offset_unit: RubyOffsetUnit::Em,
+            ..Ruby::new("かんじ")
+        };
+
+        let placement = text
+            .place_ruby(&ruby, &FuriganaSettings::default())
+            .unwrap();
+        assert_eq!(placement.base_rect, text.base_rect);
+        assert_eq!(placement.ruby_center, Vec3::new(25.0, 10.0, 0.0));
+        assert_eq!(placement.ruby_rect, Rect::new(10.0, 5.0, 40.0, 15.0));
+
+        assert!(
+            text.place_ruby(&Ruby::new(" "), &FuriganaSettings::default())
+                .is_none()
+        );
+    }
+}
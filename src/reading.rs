@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::Ruby;
+
+/// Returns the reading of the text tree under `root` (a `Text` or `Text2d` with its `TextSpan`
+/// descendants), where each section with [`Ruby`] is read as its ruby text.
+///
+/// Sections are read in the order they are laid out, and line breaks are kept.
+pub fn reading_text_of(world: &World, root: Entity) -> String {
+    text_sections(world, root)
+        .into_iter()
+        .map(|(text, ruby)| ruby.map_or(text, |ruby| ruby.rt.as_str()))
+        .collect()
+}
+
+/// Collects the sections of the text tree under `root` with their [`Ruby`], in layout order
+/// (depth-first, the same as `TextReader` iterates spans).
+pub(crate) fn text_sections(world: &World, root: Entity) -> Vec<(&str, Option<&Ruby>)> {
+    let mut sections = vec![];
+    let Ok(root_ref) = world.get_entity(root) else {
+        return sections;
+    };
+
+    let text = root_ref.get::<Text>().map(|text| text.0.as_str());
+    #[cfg(feature = "text2d")]
+    let text = text.or_else(|| root_ref.get::<Text2d>().map(|text| text.0.as_str()));
+
+    if let Some(text) = text {
+        sections.push((text, root_ref.get::<Ruby>()));
+        collect_spans(&mut sections, world, root);
+    }
+
+    sections
+}
+
+fn collect_spans<'w>(
+    sections: &mut Vec<(&'w str, Option<&'w Ruby>)>,
+    world: &'w World,
+    parent: Entity,
+) {
+    let Some(children) = world.get::<Children>(parent) else {
+        return;
+    };
+
+    for child in children {
+        if let Some(span) = world.get::<TextSpan>(*child) {
+            sections.push((&span.0, world.get::<Ruby>(*child)));
+            collect_spans(sections, world, *child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_text_of_nested_spans() {
+        let mut world = World::new();
+        let root = world
+            .spawn((
+                Text::new("今日"),
+                Ruby::new("きょう"),
+                children![
+                    (
+                        TextSpan::new("は"),
+                        children![
+                            (
+                                TextSpan::new("晴"),
+                                Ruby::new("は"),
+                                children![TextSpan::new("れ\n")],
+                            ),
+                            TextSpan::new("明日"),
+                        ],
+                    ),
+                    (TextSpan::new("雨"), Ruby::new("あめ")),
+                    // Not part of the text
+                    Name::new("unrelated"),
+                ],
+            ))
+            .id();
+
+        assert_eq!(reading_text_of(&world, root), "きょうははれ\n明日あめ");
+    }
+}
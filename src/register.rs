@@ -0,0 +1,102 @@
+use bevy::{ecs::component::Components, prelude::*};
+
+#[cfg(feature = "text2d")]
+use crate::LinkedRubyText2d;
+use crate::{
+    LinkedRubyStackTexts, LinkedRubyText, LinkedRubyTranslationText, Ruby, RubyStack,
+    RubyTranslation,
+};
+
+/// Extension trait for [`App`] to support ruby on custom text components.
+pub trait RubyAppExt {
+    /// Spawns the ruby text of a [`Ruby`] (or [`RubyTranslation`], [`RubyStack`]) already on an
+    /// entity when `C` is added to it.
+    ///
+    /// Ruby text is spawned when [`Ruby`] is added along with `Text`, `Text2d` or `TextSpan`, so a
+    /// custom text component bringing those in (e.g. with `#[require(Text)]`) needs to be
+    /// registered if it may be inserted after [`Ruby`].
+    fn register_ruby_for<C: Component>(&mut self) -> &mut Self;
+}
+
+impl RubyAppExt for App {
+    fn register_ruby_for<C: Component>(&mut self) -> &mut Self {
+        self.add_observer(spawn_ruby_text_for::<C>)
+    }
+}
+
+#[expect(clippy::type_complexity)]
+fn spawn_ruby_text_for<C: Component>(
+    on: On<Add, C>,
+    components: &Components,
+    rubies: Query<(), Or<(With<Ruby>, With<RubyTranslation>, With<RubyStack>)>>,
+    mut commands: Commands,
+) {
+    // Added together with `C`, in which case the ruby text is spawned as usual
+    let added_together = [
+        components.component_id::<Ruby>(),
+        components.component_id::<RubyTranslation>(),
+        components.component_id::<RubyStack>(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|id| on.trigger().components.contains(&id));
+    if added_together || !rubies.contains(on.entity) {
+        return;
+    }
+
+    let entity = on.entity;
+    commands.queue(move |world: &mut World| {
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return;
+        };
+
+        #[cfg(feature = "text2d")]
+        let has_ruby_text =
+            entity.contains::<LinkedRubyText>() || entity.contains::<LinkedRubyText2d>();
+        #[cfg(not(feature = "text2d"))]
+        let has_ruby_text = entity.contains::<LinkedRubyText>();
+
+        // Reinserted to spawn the ruby text the usual way
+        if !has_ruby_text && let Some(ruby) = entity.take::<Ruby>() {
+            entity.insert(ruby);
+        }
+        if !entity.contains::<LinkedRubyTranslationText>()
+            && let Some(translation) = entity.take::<RubyTranslation>()
+        {
+            entity.insert(translation);
+        }
+        if !entity.contains::<LinkedRubyStackTexts>()
+            && let Some(stack) = entity.take::<RubyStack>()
+        {
+            entity.insert(stack);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RubyText;
+
+    #[derive(Component)]
+    #[require(Text)]
+    struct Label;
+
+    #[test]
+    fn test_register_ruby_for() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .register_ruby_for::<Label>();
+
+        let world = app.world_mut();
+        let later = world.spawn(Ruby::new("later")).id();
+        world.entity_mut(later).insert(Label);
+        let linked = world.get::<LinkedRubyText>(later).unwrap();
+        assert_eq!(world.get::<Text>(linked.entity()).unwrap().0, "later");
+
+        // Not spawned twice when added together
+        world.spawn((Label, Ruby::new("together")));
+        world.spawn((Ruby::new("together"), Label));
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 3);
+    }
+}
@@ -0,0 +1,69 @@
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Component to reveal the [`Ruby`](crate::Ruby) (and [`RubyTranslation`](crate::RubyTranslation),
+/// [`RubyStack`](crate::RubyStack)) of a `Text`, `Text2d`, or `TextSpan` along with its
+/// characters, e.g. for typewriter dialogue.
+///
+/// The ruby text is revealed in proportion to the characters of the base text shown, so with
+/// mono-ruby (see [`Rubies::mono`](crate::Rubies::mono)) each reading appears with its own
+/// character. The partially revealed ruby text is aligned by its own width, so
+/// [`RubyAlign::Start`](crate::RubyAlign::Start) keeps it from shifting as it grows.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component, Default)]
+pub struct RubyReveal {
+    /// Number of characters (not bytes) of the base text shown so far.
+    pub base_chars_shown: usize,
+}
+
+impl RubyReveal {
+    pub fn new(base_chars_shown: usize) -> Self {
+        Self { base_chars_shown }
+    }
+}
+
+/// Leading part of ruby text `rt` revealed along with `reveal` of the base text `base`.
+pub(crate) fn revealed_text<'a>(
+    rt: Cow<'a, str>,
+    reveal: Option<&RubyReveal>,
+    base: &str,
+) -> Cow<'a, str> {
+    let Some(reveal) = reveal else {
+        return rt;
+    };
+    let base_chars = base.chars().count();
+    if reveal.base_chars_shown >= base_chars {
+        return rt;
+    }
+
+    let graphemes = rt.graphemes(true).count();
+    let shown = graphemes * reveal.base_chars_shown / base_chars;
+    Cow::Owned(rt.graphemes(true).take(shown).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revealed_text() {
+        let revealed = |shown| {
+            revealed_text(
+                Cow::Borrowed("とうきょう"),
+                Some(&RubyReveal::new(shown)),
+                "東京",
+            )
+        };
+        assert_eq!(revealed(0), "");
+        assert_eq!(revealed(1), "とう");
+        assert_eq!(revealed(2), "とうきょう");
+        assert_eq!(revealed(5), "とうきょう");
+
+        assert_eq!(
+            revealed_text(Cow::Borrowed("かんじ"), None, "漢字"),
+            "かんじ"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use core::ops::Range;
+
+use bevy::prelude::*;
+
+use crate::Ruby;
+
+pub fn plugin(app: &mut App) {
+    app.add_observer(expand_rubies);
+
+    #[cfg(feature = "text2d")]
+    app.add_observer(expand_rubies_2d);
+}
+
+/// Component to add multiple ruby texts to a `Text` or `Text2d` by character ranges.
+///
+/// When added, the text is split into `TextSpan` children so that each annotated range gets its own
+/// [`Ruby`]. Ranges must not overlap; invalid entries are skipped with a warning.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Rubies(pub Vec<RubyEntry>);
+
+impl Rubies {
+    /// Mono-ruby: a [`Ruby::mono`] annotating a character per reading from the start of the text,
+    /// so that each reading is centered over its own character (e.g. `["とう", "きょう"]` for
+    /// "東京"). Empty readings (e.g. for okurigana) leave their character without ruby.
+    pub fn mono(readings: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let readings = readings
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<String>>();
+        if readings.iter().all(String::is_empty) {
+            return Self::default();
+        }
+        Self(vec![RubyEntry {
+            range: 0..readings.len(),
+            ruby: Ruby::mono(readings),
+        }])
+    }
+}
+
+/// A [`Ruby`] annotating a range of characters in the base text.
+#[derive(Clone, Debug)]
+pub struct RubyEntry {
+    /// Range of characters (not bytes) in the base text.
+    pub range: Range<usize>,
+    pub ruby: Ruby,
+}
+
+impl RubyEntry {
+    pub fn new(range: Range<usize>, rt: impl Into<String>) -> Self {
+        Self {
+            range,
+            ruby: Ruby::new(rt),
+        }
+    }
+}
+
+fn expand_rubies(
+    on: On<Add, Rubies>,
+    mut texts: Query<(&Rubies, &mut Text, &TextFont, &TextColor)>,
+    commands: Commands,
+) {
+    if let Ok((rubies, mut text, text_font, text_color)) = texts.get_mut(on.entity) {
+        spawn_ruby_spans(
+            on.entity,
+            commands,
+            &mut text.0,
+            &rubies.0,
+            text_font,
+            *text_color,
+        );
+    }
+}
+
+#[cfg(feature = "text2d")]
+fn expand_rubies_2d(
+    on: On<Add, Rubies>,
+    mut texts: Query<(&Rubies, &mut Text2d, &TextFont, &TextColor)>,
+    commands: Commands,
+) {
+    if let Ok((rubies, mut text, text_font, text_color)) = texts.get_mut(on.entity) {
+        spawn_ruby_spans(
+            on.entity,
+            commands,
+            &mut text.0,
+            &rubies.0,
+            text_font,
+            *text_color,
+        );
+    }
+}
+
+/// Zero-width joiner
+const ZWJ: char = '\u{200D}';
+
+fn spawn_ruby_spans(
+    root: Entity,
+    mut commands: Commands,
+    text: &mut String,
+    entries: &[RubyEntry],
+    text_font: &TextFont,
+    text_color: TextColor,
+) {
+    let mut segments = split_text(text, entries).into_iter();
+
+    // The leading unannotated segment stays in the root text
+    let Some((head, _)) = segments.next() else {
+        return;
+    };
+    *text = head;
+
+    let span_ids = segments
+        .map(|(span_text, ruby)| {
+            let mut span = commands.spawn((
+                TextSpan(span_text),
+                text_font.clone(),
+                text_color,
+                ChildOf(root),
+            ));
+            if let Some(ruby) = ruby {
+                span.insert(ruby);
+            }
+            span.id()
+        })
+        .collect::<Vec<_>>();
+
+    // Keep the spans before any existing children
+    commands.entity(root).insert_children(0, &span_ids);
+}
+
+/// Splits `text` into segments, where annotated segments carry their [`Ruby`].
+/// The first segment is always unannotated (possibly empty).
+///
+/// Ranges are widened so that zero-width joiner sequences (e.g. "👨‍👩‍👧") are never split.
+fn split_text(text: &str, entries: &[RubyEntry]) -> Vec<(String, Option<Ruby>)> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let char_count = chars.len();
+    // Whether a segment boundary at `char_index` would split a ZWJ sequence
+    let splits_zwj_sequence = |char_index: usize| {
+        (0 < char_index && char_index < char_count)
+            && (chars[char_index - 1] == ZWJ || chars[char_index] == ZWJ)
+    };
+    let byte_index = |char_index: usize| {
+        text.char_indices()
+            .nth(char_index)
+            .map_or(text.len(), |(i, _)| i)
+    };
+
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.range.start);
+
+    let mut segments = vec![];
+    let mut current = 0;
+    for entry in entries {
+        let Range { mut start, mut end } = entry.range;
+        while splits_zwj_sequence(start) {
+            start -= 1;
+        }
+        while splits_zwj_sequence(end) {
+            end += 1;
+        }
+        if start < current || entry.range.is_empty() || end > char_count {
+            warn!(
+                "Skipping ruby {:?} with invalid range {:?}",
+                entry.ruby.rt, entry.range
+            );
+            continue;
+        }
+
+        let plain = &text[byte_index(current)..byte_index(start)];
+        if segments.is_empty() || !plain.is_empty() {
+            segments.push((plain.to_string(), None));
+        }
+        segments.push((
+            text[byte_index(start)..byte_index(end)].to_string(),
+            Some(entry.ruby.clone()),
+        ));
+        current = end;
+    }
+
+    let rest = &text[byte_index(current)..];
+    if segments.is_empty() || !rest.is_empty() {
+        segments.push((rest.to_string(), None));
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LinkedRubyText;
+
+    #[test]
+    fn test_rubies_expand_into_spans() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("東京へ行く"),
+                Rubies(vec![
+                    RubyEntry::new(3..4, "い"),
+                    RubyEntry::new(0..2, "とうきょう"),
+                ]),
+            ))
+            .id();
+        app.world_mut().flush();
+
+        let world = app.world();
+        assert_eq!(world.get::<Text>(text_entity).unwrap().0, "");
+
+        let children = world.get::<Children>(text_entity).unwrap();
+        let spans = children
+            .iter()
+            .map(|child| {
+                let span = world.get::<TextSpan>(child).unwrap().0.as_str();
+                let rt = world
+                    .get::<LinkedRubyText>(child)
+                    .map(|linked| world.get::<Text>(linked.entity()).unwrap().0.as_str());
+                (span, rt)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("東京", Some("とうきょう")),
+                ("へ", None),
+                ("行", Some("い")),
+                ("く", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mono_rubies() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("送り仮名を"),
+                Rubies::mono(["おく", "", "か", "な"]),
+            ))
+            .id();
+        app.world_mut().flush();
+
+        let world = app.world();
+        let children = world.get::<Children>(text_entity).unwrap();
+        let spans = children
+            .iter()
+            .map(|child| {
+                let span = world.get::<TextSpan>(child).unwrap().0.as_str();
+                (span, world.get::<Ruby>(child))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("送り仮名", Some(&Ruby::mono(["おく", "", "か", "な"]))),
+                ("を", None),
+            ]
+        );
+        let ruby = spans[0].1.unwrap();
+        assert_eq!(ruby.rt, "おくかな");
+
+        assert!(Rubies::mono(["", ""]).0.is_empty());
+    }
+
+    #[test]
+    fn test_split_text_keeps_zwj_sequences() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let text = format!("家族{family}です");
+
+        // Only "👨" of the sequence is covered by the range
+        let segments = split_text(&text, &[RubyEntry::new(2..3, "かぞく")]);
+        assert_eq!(
+            segments
+                .iter()
+                .map(|(text, ruby)| (text.as_str(), ruby.as_ref().map(|ruby| ruby.rt.as_str())))
+                .collect::<Vec<_>>(),
+            [("家族", None), (family, Some("かぞく")), ("です", None)]
+        );
+    }
+
+    #[test]
+    fn test_split_text_with_astral_plane_characters() {
+        // "𠮷" (U+20BB7) is outside the BMP, taking 4 bytes in UTF-8
+        let segments = split_text(
+            "𠮷野家𩸽",
+            &[
+                RubyEntry::new(0..2, "よしの"),
+                RubyEntry::new(3..4, "ほっけ"),
+            ],
+        );
+        assert_eq!(
+            segments
+                .iter()
+                .map(|(text, ruby)| (text.as_str(), ruby.as_ref().map(|ruby| ruby.rt.as_str())))
+                .collect::<Vec<_>>(),
+            [
+                // Text of the root
+                ("", None),
+                ("𠮷野", Some("よしの")),
+                ("家", None),
+                ("𩸽", Some("ほっけ"))
+            ]
+        );
+    }
+}
@@ -1,27 +1,65 @@
 use bevy::{
+    ecs::{
+        entity::{EntityHashMap, EntityHashSet},
+        relationship::RelationshipTarget,
+    },
     prelude::*,
-    text::{Text2dUpdateSystems, TextLayoutInfo},
+    sprite::{Anchor, Text2dShadow, update_text2d_layout},
+    text::{Text2dUpdateSystems, TextBounds, TextLayoutInfo, detect_text_needs_rerender},
 };
 
-use crate::{Ruby, RubyAlign, RubyPosition, TextRootEntity};
+use crate::{
+    Annotation, AnyRubyText, FuriganaDisabled, FuriganaSettings, FuriganaSystems,
+    LinkedRubyStackTexts, LinkedRubyTranslationText, NoRuby, OuterAnnotations, PlacedRoots, Ruby,
+    RubyFadeIn, RubyOutline, RubyOverhang, RubyPalette, RubyPlacement, RubySlot, RubyStack,
+    RubyStackText, RubyTranslation, RubyTranslationText, TextRootEntity, annotation_of,
+    annotation_ruby, center_mono_ruby,
+    layout::{SectionRectsCache, TextBlock, justify_glyphs, text_lines},
+    linked_annotation_texts,
+    merge::{MergeableSpan, MergedRuby, RubyMergedInto, merge_leader, merged_display_text},
+    mono_ruby_base_centers, non_cjk_sections, place_block_rubies,
+    placement::{RubyPositioned, RubySettling, RubyStabilized, RubyTextSpawned, bounding_rect},
+    reveal::{RubyReveal, revealed_text},
+    root_ruby_hidden, ruby_budget_exceeded, ruby_text_color, ruby_text_font,
+};
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
         PostUpdate,
         (
-            (update_ruby_2d_visibility, update_ruby_text_2d),
-            update_ruby_2d,
+            (
+                update_ruby_2d_visibility,
+                (update_ruby_text_2d, update_ruby_outline_2d).chain(),
+            )
+                .in_set(FuriganaSystems::Spawn),
+            (update_ruby_2d, track_ruby_settling_2d)
+                .chain()
+                .in_set(FuriganaSystems::Update),
         )
-            .chain()
             .before(Text2dUpdateSystems),
     )
-    .add_observer(add_ruby_2d)
-    .add_observer(add_ruby_text_span_2d);
+    .add_systems(
+        PostUpdate,
+        update_ruby_background_2d
+            .after(Text2dUpdateSystems)
+            .in_set(FuriganaSystems::Update),
+    )
+    .add_observer(add_ruby_2d::<Ruby>)
+    .add_observer(add_ruby_2d::<RubyTranslation>)
+    .add_observer(add_ruby_2d::<RubyStack>)
+    .add_observer(add_ruby_text_span_2d::<Ruby>)
+    .add_observer(add_ruby_text_span_2d::<RubyTranslation>)
+    .add_observer(add_ruby_text_span_2d::<RubyStack>)
+    .add_observer(remove_ruby_text_2d)
+    .add_observer(remove_slot_ruby_text_2d::<Ruby>)
+    .add_observer(remove_slot_ruby_text_2d::<RubyTranslation>)
+    .add_observer(remove_slot_ruby_text_2d::<RubyStack>);
 }
 
 /// Component for 2D ruby text.
 /// Automatically spawned when [`Ruby`] component is added along with `Text2d` or `TextSpan`.
 #[derive(Component, Clone, Copy)]
+#[require(RubyOverhang, RubySettling)]
 #[relationship(relationship_target = LinkedRubyText2d)]
 pub struct RubyText2d(
     /// Entity of the corresponding `Ruby` component.
@@ -39,109 +77,394 @@ impl LinkedRubyText2d {
     }
 }
 
-pub fn add_ruby_2d(
-    on: On<Add, Ruby>,
-    ruby: Query<(&Ruby, &TextFont, &Transform, &TextColor), With<Text2d>>,
-    commands: Commands,
+/// Padded background box drawn behind 2D ruby text.
+/// See [`FuriganaSettings::ruby_background_padding`].
+#[derive(Component)]
+struct RubyBackground2d;
+
+/// Copy of 2D ruby text drawn behind it to make up its [`RubyOutline`].
+#[derive(Component)]
+struct RubyOutline2d {
+    direction: Vec2,
+}
+
+/// [`RubySlot`] on 2D text.
+pub(crate) trait RubySlot2d: RubySlot {
+    /// Relationship target on the base entity, linking to the annotation texts.
+    type Linked: RelationshipTarget;
+
+    /// Links the annotation text of the `index`-th annotation of the slot to the base entity.
+    fn link(base: Entity, index: usize) -> impl Bundle;
+}
+
+impl RubySlot2d for Ruby {
+    type Linked = LinkedRubyText2d;
+
+    fn link(base: Entity, _index: usize) -> impl Bundle {
+        RubyText2d(base)
+    }
+}
+
+impl RubySlot2d for RubyTranslation {
+    type Linked = LinkedRubyTranslationText;
+
+    fn link(base: Entity, _index: usize) -> impl Bundle {
+        RubyTranslationText(base)
+    }
+}
+
+impl RubySlot2d for RubyStack {
+    type Linked = LinkedRubyStackTexts;
+
+    fn link(base: Entity, index: usize) -> impl Bundle {
+        RubyStackText { base, index }
+    }
+}
+
+/// Spawns the ruby text of a `Text2d` with `S`, whichever of them is added last (e.g. when loaded
+/// from a scene one component at a time).
+#[expect(clippy::type_complexity)]
+pub(crate) fn add_ruby_2d<S: RubySlot2d>(
+    on: On<Add, (S, Text2d)>,
+    ruby: Query<
+        (&S, &TextFont, &Transform, &TextColor),
+        (With<Text2d>, Without<NoRuby>, Without<S::Linked>),
+    >,
+    settings: Res<FuriganaSettings>,
+    ruby_texts: Query<(), AnyRubyText>,
+    mut commands: Commands,
 ) {
-    if let Ok((ruby, text_font, transform, text_color)) = ruby.get(on.entity) {
-        create_ruby_text_2d(
-            on,
-            commands,
-            ruby,
-            text_font,
-            ruby.font_size_scale,
-            transform,
-            *text_color,
-        );
+    if let Ok((slot, text_font, transform, text_color)) = ruby.get(on.entity) {
+        let ruby_text_count = ruby_texts.iter().count();
+        for (index, ruby) in slot.rubies().iter().enumerate() {
+            if ruby_budget_exceeded(&settings, ruby_text_count + index) {
+                return;
+            }
+
+            create_ruby_text_2d::<S>(
+                on.entity,
+                index,
+                &mut commands,
+                on.entity,
+                ruby,
+                text_font,
+                &settings,
+                transform,
+                *text_color,
+            );
+        }
+    }
+}
+
+/// Despawns the annotation text of a base whose `S` is removed, so that no stale ruby is left.
+pub(crate) fn remove_slot_ruby_text_2d<S: RubySlot2d>(
+    on: On<Remove, S>,
+    linked: Query<&S::Linked>,
+    mut commands: Commands,
+) {
+    if let Ok(linked) = linked.get(on.entity) {
+        for rt_id in linked.iter() {
+            // Already despawned along with the base, if it is being despawned
+            commands.entity(rt_id).try_despawn();
+        }
+    }
+}
+
+/// Despawns the ruby text of a base whose `Text2d` is removed, as it can no longer be placed.
+fn remove_ruby_text_2d(
+    on: On<Remove, Text2d>,
+    linked: Query<(
+        Option<&LinkedRubyText2d>,
+        Option<&LinkedRubyTranslationText>,
+        Option<&LinkedRubyStackTexts>,
+    )>,
+    mut commands: Commands,
+) {
+    if let Ok(linked) = linked.get(on.entity) {
+        for rt_id in linked_annotation_texts(linked) {
+            // Already despawned along with the base, if it is being despawned
+            commands.entity(rt_id).try_despawn();
+        }
     }
 }
 
-pub fn add_ruby_text_span_2d(
-    on: On<Add, Ruby>,
-    ruby: Query<&Ruby, With<TextSpan>>,
-    text_config: Query<(&TextFont, &TextColor)>,
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn add_ruby_text_span_2d<S: RubySlot2d>(
+    on: On<Add, (S, TextSpan, ChildOf)>,
+    ruby: Query<&S, (With<TextSpan>, Without<NoRuby>, Without<S::Linked>)>,
+    text_fonts: Query<&TextFont>,
+    text_colors: Query<&TextColor>,
     ancestors: Query<&ChildOf>,
     text_2d: Query<&Transform, With<Text2d>>,
-    commands: Commands,
+    settings: Res<FuriganaSettings>,
+    ruby_texts: Query<(), AnyRubyText>,
+    children: Query<&Children>,
+    spans: Query<MergeableSpan, Without<NoRuby>>,
+    mut commands: Commands,
 ) {
-    if let Ok(ruby) = ruby.get(on.entity) {
+    if let Ok(slot) = ruby.get(on.entity) {
         let Ok(&ChildOf(parent)) = ancestors.get(on.entity) else {
             return;
         };
 
-        let Ok((text_font, color)) = text_config.get(on.entity) else {
+        // Only a span right under a `Text2d` is part of its text, unlike one under another child
+        // of it (e.g. a sprite), or under UI text
+        let Ok(transform) = text_2d.get(parent) else {
             return;
         };
 
-        let Ok(transform) = text_2d.get(parent) else {
+        // A span without its own style (e.g. removed from it) falls back to that of its parent
+        let (Ok(text_font), Ok(color)) = (
+            text_fonts
+                .get(on.entity)
+                .or_else(|_| text_fonts.get(parent)),
+            text_colors
+                .get(on.entity)
+                .or_else(|_| text_colors.get(parent)),
+        ) else {
             return;
         };
 
-        create_ruby_text_2d(
-            on,
-            commands,
-            ruby,
-            text_font,
-            ruby.font_size_scale,
-            transform,
-            *color,
-        );
+        if S::MERGEABLE
+            && settings.merge_adjacent_ruby
+            && let Ok(siblings) = children.get(parent)
+            && let Some(leader) = merge_leader(on.entity, siblings, &spans)
+        {
+            commands.entity(on.entity).insert(RubyMergedInto(leader));
+            return;
+        }
+
+        let ruby_text_count = ruby_texts.iter().count();
+        for (index, ruby) in slot.rubies().iter().enumerate() {
+            if ruby_budget_exceeded(&settings, ruby_text_count + index) {
+                return;
+            }
+
+            create_ruby_text_2d::<S>(
+                on.entity,
+                index,
+                &mut commands,
+                parent,
+                ruby,
+                text_font,
+                &settings,
+                transform,
+                *color,
+            );
+        }
     }
 }
 
-fn create_ruby_text_2d(
-    on: On<Add, Ruby>,
-    mut commands: Commands,
+#[expect(clippy::too_many_arguments)]
+fn create_ruby_text_2d<S: RubySlot2d>(
+    base: Entity,
+    index: usize,
+    commands: &mut Commands,
+    text_root: Entity,
     ruby: &Ruby,
     text_font: &TextFont,
-    font_size_scale: f32,
+    settings: &FuriganaSettings,
     transform: &Transform,
     text_color: TextColor,
 ) {
-    commands.spawn((
-        RubyText2d(on.entity),
-        Text2d(ruby.rt.clone()),
-        ruby_text_font(text_font, font_size_scale),
+    // Order higher (or lower) than original text
+    let z_offset = if settings.ruby_behind_base {
+        -0.01
+    } else {
+        0.01
+    };
+
+    let mut ruby_text = commands.spawn((
+        S::link(base, index),
+        Text2d(ruby.display_text(settings.blank_ruby).into_owned()),
+        TextLayout::new_with_no_wrap(),
+        RubyFadeIn::default(),
+        ruby_text_font(ruby, text_font, settings),
         ruby.color.unwrap_or(text_color),
-        // Order higher than original text
-        Transform::from_translation(Vec3::new(0.0, 0.0, transform.translation.z + 0.01)),
+        // Hidden until placed
+        Visibility::Hidden,
     ));
+    set_ruby_shadow(&mut ruby_text, ruby);
+    if settings.ruby_2d_child_of_base {
+        ruby_text.insert((
+            ChildOf(text_root),
+            Transform::from_translation(Vec3::new(0.0, 0.0, z_offset)),
+        ));
+    } else {
+        ruby_text.insert(Transform::from_translation(Vec3::new(
+            0.0,
+            0.0,
+            transform.translation.z + z_offset,
+        )));
+    }
+    let rt_id = ruby_text.id();
+    commands.write_message(RubyTextSpawned {
+        base,
+        ruby_text: rt_id,
+    });
 }
 
-fn ruby_text_font(text_font: &TextFont, font_size_scale: f32) -> TextFont {
-    TextFont {
-        font_size: text_font.font_size * font_size_scale,
-        ..text_font.clone()
-    }
+fn set_ruby_shadow(ruby_text: &mut EntityCommands, ruby: &Ruby) {
+    match ruby.shadow {
+        Some(shadow) => ruby_text.insert(Text2dShadow {
+            // Y up in 2D
+            offset: shadow.offset * Vec2::new(1.0, -1.0),
+            color: shadow.color,
+        }),
+        None => ruby_text.remove::<Text2dShadow>(),
+    };
 }
 
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_ruby_text_2d(
-    mut ruby_text: Query<(&RubyText2d, &mut Text2d, &mut TextFont, &mut TextColor), Without<Ruby>>,
-    ruby: Query<(Ref<Ruby>, Ref<TextFont>, &TextColor)>,
+    mut ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText2d, &RubyTranslationText, &RubyStackText)>,
+            &mut Text2d,
+            &mut TextFont,
+            &mut TextColor,
+            Option<&mut RubyFadeIn>,
+        ),
+        Without<Ruby>,
+    >,
+    ruby: Query<
+        (
+            Option<Ref<Ruby>>,
+            Option<Ref<RubyTranslation>>,
+            Option<Ref<RubyStack>>,
+            Ref<TextFont>,
+            &TextColor,
+            Option<&TextBackgroundColor>,
+            Option<Ref<RubyReveal>>,
+        ),
+        (
+            Without<RubyText2d>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    base_texts: Query<
+        AnyOf<(&Text2d, &TextSpan)>,
+        (
+            Without<RubyText2d>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    merged: Query<&MergedRuby>,
+    rubies: Query<&Ruby>,
+    mut removed_reveals: RemovedComponents<RubyReveal>,
+    settings: Res<FuriganaSettings>,
+    palette: Res<RubyPalette>,
+    time: Res<Time>,
+    mut commands: Commands,
 ) {
-    for (&RubyText2d(rt_id), mut text, mut ruby_font, mut ruby_text_color) in &mut ruby_text {
-        if let Ok((ruby, text_font, text_color)) = ruby.get(rt_id) {
-            if ruby.is_changed() && text.0 != ruby.rt {
-                text.0.clone_from(&ruby.rt);
+    let removed_reveals = removed_reveals.read().collect::<EntityHashSet>();
+    for (rt_entity, link, mut text, mut ruby_font, mut rt_color, fade_in) in &mut ruby_text {
+        let Some((rt_id, annotation)) = annotation_of(link) else {
+            continue;
+        };
+
+        if let Ok((reading, translation, stack, text_font, &text_color, background, reveal)) =
+            ruby.get(rt_id)
+        {
+            let Some((ruby_changed, ruby)) =
+                annotation_ruby(annotation, reading, translation, stack)
+            else {
+                continue;
+            };
+
+            let merged = (annotation == Annotation::Reading)
+                .then(|| merged.get(rt_id).ok())
+                .flatten();
+            let rt = merged_display_text(ruby, merged, &rubies, settings.blank_ruby);
+            let base_text = match base_texts.get(rt_id) {
+                Ok((Some(Text2d(text)), _) | (_, Some(TextSpan(text)))) => text.as_str(),
+                _ => "",
+            };
+            let revealed = reveal.as_ref().is_some_and(|reveal| reveal.is_changed())
+                || removed_reveals.contains(&rt_id);
+            let rt = revealed_text(rt, reveal.as_deref(), base_text);
+            // Spans merged into this one may change at any time
+            if (ruby_changed || settings.is_changed() || merged.is_some() || revealed)
+                && text.0 != rt
+            {
+                text.0 = rt.into_owned();
+            }
+
+            if text_font.is_changed() || ruby_changed || settings.is_changed() {
+                *ruby_font = ruby_text_font(ruby, &text_font, &settings);
             }
 
-            if text_font.is_changed() {
-                *ruby_font = ruby_text_font(&text_font, ruby.font_size_scale);
+            if ruby_changed {
+                set_ruby_shadow(&mut commands.entity(rt_entity), ruby);
             }
 
-            *ruby_text_color = ruby.color.unwrap_or(*text_color);
+            *rt_color = ruby_text_color(ruby, text_color, background, &palette, &settings);
+
+            if let Some(mut fade_in) = fade_in {
+                if let Some(alpha) = fade_in.alpha(time.elapsed(), settings.ruby_fade_in) {
+                    let color = rt_color.0;
+                    rt_color.0 = color.with_alpha(color.alpha() * alpha);
+                } else {
+                    commands.entity(rt_entity).remove::<RubyFadeIn>();
+                }
+            }
         }
     }
 }
 
+/// Sends [`RubyPositioned`] once the ruby text is placed, and [`RubyStabilized`] once it stays in
+/// place for a frame.
+#[expect(clippy::type_complexity)]
+fn track_ruby_settling_2d(
+    mut ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText2d, &RubyTranslationText, &RubyStackText)>,
+            &mut RubySettling,
+            &Transform,
+            &TextLayoutInfo,
+            &Visibility,
+        ),
+        With<Text2d>,
+    >,
+    mut positioned: MessageWriter<RubyPositioned>,
+    mut stabilized: MessageWriter<RubyStabilized>,
+) {
+    for (rt_id, link, mut settling, transform, layout_info, visibility) in &mut ruby_text {
+        let Some((base, _)) = annotation_of(link) else {
+            continue;
+        };
+        let placement = (*visibility != Visibility::Hidden)
+            .then_some((transform.translation, layout_info.size));
+        if settling.position(placement.is_some()) {
+            positioned.write(RubyPositioned {
+                base,
+                ruby_text: rt_id,
+            });
+        }
+        if settling.settle(placement) {
+            stabilized.write(RubyStabilized { base, ruby: rt_id });
+        }
+    }
+}
+
+#[expect(clippy::type_complexity)]
 pub fn update_ruby_2d_visibility(
-    ruby_text: Query<(Entity, &RubyText2d)>,
+    ruby_text: Query<(
+        Entity,
+        AnyOf<(&RubyText2d, &RubyTranslationText, &RubyStackText)>,
+    )>,
     text_root: Query<TextRootEntity>,
     inherited_vis: Query<Ref<InheritedVisibility>>,
     mut visibility: Query<&mut Visibility>,
 ) {
-    for (rt_id, &RubyText2d(src_id)) in &ruby_text {
+    for (rt_id, link) in &ruby_text {
+        let Some((src_id, _)) = annotation_of(link) else {
+            continue;
+        };
         let Some(text_entity) = text_root.get(src_id).ok().and_then(|tr| tr.get()) else {
             continue;
         };
@@ -165,90 +488,637 @@ pub fn update_ruby_2d_visibility(
     }
 }
 
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_ruby_2d(
-    mut text_layouts: Query<(&TextLayoutInfo, &mut Visibility)>,
-    ruby_query: Query<(Entity, Ref<Ruby>, &LinkedRubyText2d, TextRootEntity), Without<RubyText2d>>,
-    _ancestors: Query<&ChildOf>,
-    mut ruby_transforms: Query<&mut Transform, (With<RubyText2d>, Without<Ruby>)>,
-    text_2d_transforms: Query<&GlobalTransform, With<Text2d>>,
+    mut text_layouts: Query<(&mut TextLayoutInfo, &mut Visibility)>,
+    ruby_query: Query<(
+        Entity,
+        Ref<Ruby>,
+        &LinkedRubyText2d,
+        TextRootEntity,
+        Option<Ref<MergedRuby>>,
+    )>,
+    outer_annotations: OuterAnnotations,
+    mut ruby_transforms: Query<
+        (&mut Transform, Option<&ChildOf>),
+        (
+            Or<(
+                With<RubyText2d>,
+                With<RubyTranslationText>,
+                With<RubyStackText>,
+            )>,
+            Without<Ruby>,
+        ),
+    >,
+    text_2d_transforms: Query<(Ref<GlobalTransform>, Ref<TextBounds>, Ref<Anchor>), With<Text2d>>,
+    ancestors: Query<&ChildOf>,
+    disabled: Query<(), With<FuriganaDisabled>>,
+    visibilities: Query<&Visibility, Without<TextLayoutInfo>>,
+    mut overhangs: Query<&mut RubyOverhang>,
+    mut text_reader: Text2dReader,
+    text_fonts: Query<&TextFont>,
+    fonts: Res<Assets<Font>>,
+    settings: Res<FuriganaSettings>,
+    mut section_rects: Local<SectionRectsCache>,
+    mut placed_roots: Local<PlacedRoots>,
 ) {
-    for (text_entity, ruby, &LinkedRubyText2d(rt_id), text_root) in &ruby_query {
-        let Some(text_root_id) = text_root.get() else {
+    // Rubies are placed per text root, so that they can be laid out against each other
+    let mut rubies_by_root =
+        EntityHashMap::<Vec<(Entity, &Ruby, Entity, Option<&MergedRuby>)>>::default();
+    let mut roots_with_changed_ruby = EntityHashSet::default();
+    // Readings first, so that annotations on the same side are stacked outward of them
+    let outer_annotations =
+        outer_annotations
+            .iter()
+            .map(|(text_entity, ruby_changed, ruby, rt_id, text_root_id)| {
+                (text_entity, ruby_changed, ruby, rt_id, text_root_id, None)
+            });
+    for (text_entity, ruby_changed, ruby, rt_id, text_root_id, merged) in ruby_query
+        .iter()
+        .map(|(text_entity, ruby, linked, text_root, merged)| {
+            (
+                text_entity,
+                ruby.is_changed() || merged.as_ref().is_some_and(DetectChanges::is_changed),
+                ruby.into_inner(),
+                linked.entity(),
+                text_root.get(),
+                merged.map(Ref::into_inner),
+            )
+        })
+        .chain(outer_annotations)
+    {
+        let Some(text_root_id) = text_root_id else {
             error!("No text root entity for {text_entity:?}");
             continue;
         };
+        if ruby_changed {
+            roots_with_changed_ruby.insert(text_root_id);
+        }
+        rubies_by_root
+            .entry(text_root_id)
+            .or_default()
+            .push((text_entity, ruby, rt_id, merged));
+    }
 
-        let Ok((layout_info, visibility)) = text_layouts.get_mut(text_root_id) else {
-            continue;
+    for (text_root_id, rubies) in rubies_by_root {
+        // Only `get_mut` tells whether the layout changed, and it is not changed by that alone
+        let mut layout_changed = |entity| {
+            text_layouts
+                .get_mut(entity)
+                .is_ok_and(|(layout_info, _)| layout_info.is_changed())
         };
-
-        if *visibility == Visibility::Hidden {
-            if let Ok((_, mut ruby_vis)) = text_layouts.get_mut(rt_id) {
-                ruby_vis.set_if_neq(Visibility::Hidden);
-            }
+        let root_layout_changed = layout_changed(text_root_id);
+        let ruby_layout_changed = rubies.iter().any(|&(_, _, rt_id, _)| layout_changed(rt_id));
+        let Ok((layout_info, _)) = text_layouts.get(text_root_id) else {
             continue;
-        }
+        };
 
-        let Some(section_rect) = layout_info
-            .section_rects
-            .iter()
-            .find(|&&(id, _)| id == text_entity)
-            .map(|&(_, rect)| rect)
+        let Ok((text_global_transform, bounds, anchor)) = text_2d_transforms.get(text_root_id)
         else {
             continue;
         };
-        let section_rect = Rect::from_corners(
-            section_rect.min / layout_info.scale_factor,
-            section_rect.max / layout_info.scale_factor,
-        );
-        let text_layout_size = layout_info.size;
 
-        let Ok((ruby_layout_info, mut vis)) = text_layouts.get_mut(rt_id) else {
+        let hidden = root_ruby_hidden(text_root_id, &ancestors, &disabled, |entity| {
+            text_layouts
+                .get(entity)
+                .map(|(_, visibility)| *visibility)
+                .or_else(|_| visibilities.get(entity).copied())
+                .ok()
+        });
+
+        // Static text is left as placed, while ruby follows changes of the layout and transform of
+        // the base, and of the size of the ruby text itself. Hidden ruby is hidden every run.
+        let ruby_count = rubies.len();
+        let changed = settings.is_changed()
+            || root_layout_changed
+            || ruby_layout_changed
+            || roots_with_changed_ruby.contains(&text_root_id)
+            || text_global_transform.is_changed()
+            || bounds.is_changed()
+            || anchor.is_changed();
+        if !hidden && placed_roots.unchanged(text_root_id, ruby_count, changed) {
             continue;
+        }
+        let (text_global_transform, bounds, anchor) = (
+            text_global_transform.into_inner(),
+            bounds.into_inner(),
+            anchor.into_inner(),
+        );
+
+        let lines = text_lines(
+            text_reader
+                .iter(text_root_id)
+                .map(|(_, _, text, _, _)| text),
+        );
+        let non_cjk = non_cjk_sections(
+            text_reader
+                .iter(text_root_id)
+                .map(|(entity, _, text, _, _)| (entity, text)),
+            &settings,
+        );
+        let scale_factor = layout_info.scale_factor;
+        let top_left = text_2d_top_left(layout_info, bounds, anchor);
+        let block = TextBlock::with_section_rects(
+            layout_info,
+            section_rects.get(text_root_id, layout_info, root_layout_changed),
+            &lines,
+        );
+        let ruby_boxes = if hidden {
+            vec![None; rubies.len()]
+        } else {
+            let block_rubies = rubies
+                .iter()
+                .map(|&(text_entity, ruby, rt_id, merged)| {
+                    let ruby_size = text_layouts
+                        .get(rt_id)
+                        .ok()
+                        .map(|(ruby_layout_info, _)| ruby_layout_info.size * scale_factor);
+                    (text_entity, ruby, merged, ruby_size)
+                })
+                .collect::<Vec<_>>();
+            place_block_rubies(
+                &block,
+                &block_rubies,
+                &non_cjk,
+                &text_fonts,
+                &fonts,
+                &settings,
+            )
         };
 
-        vis.set_if_neq(Visibility::Inherited);
+        let mono_base_centers = rubies
+            .iter()
+            .map(|&(text_entity, ruby, _, merged)| {
+                mono_ruby_base_centers(
+                    &block,
+                    text_entity,
+                    ruby,
+                    merged,
+                    settings.section_rect_tie_break,
+                )
+            })
+            .collect::<Vec<_>>();
 
-        let ruby_pos_local = Vec2::new(
-            match ruby.align {
-                RubyAlign::Start => section_rect.min.x + ruby_layout_info.size.x / 2.0,
-                RubyAlign::Center => f32::midpoint(section_rect.min.x, section_rect.max.x),
-                RubyAlign::End => section_rect.max.x - ruby_layout_info.size.x / 2.0,
-            },
-            match ruby.position {
-                RubyPosition::Over => section_rect.min.y,
-                RubyPosition::Under => section_rect.max.y,
-            },
-        );
+        let mut placed = vec![];
+        for ((&(_, ruby, rt_id, _), ruby_box), base_centers) in
+            rubies.iter().zip(ruby_boxes).zip(mono_base_centers)
+        {
+            let Ok((mut ruby_layout_info, mut vis)) = text_layouts.get_mut(rt_id) else {
+                continue;
+            };
+
+            // Glyphs are positioned in physical pixels, while the laid out size is logical
+            let origin_x = ruby_layout_info.size.x * ruby_layout_info.scale_factor / 2.0;
+            if let (Some(ruby_box), Some(base_centers)) = (&ruby_box, base_centers)
+                && center_mono_ruby(
+                    ruby,
+                    &base_centers,
+                    ruby_box,
+                    ruby_layout_info.bypass_change_detection(),
+                    origin_x,
+                )
+            {
+                ruby_layout_info.set_changed();
+            }
+
+            let Some(ruby_box) = ruby_box.map(|ruby_box| ruby_box.to_logical(scale_factor)) else {
+                // Base text is hidden, has furigana disabled or no CJK text with `cjk_only`, or is not
+                // laid out yet (e.g. the font is still loading)
+                vis.set_if_neq(Visibility::Hidden);
+                continue;
+            };
+
+            // Reveal once the ruby text itself has been laid out
+            if ruby_layout_info.size != Vec2::ZERO {
+                vis.set_if_neq(Visibility::Inherited);
+            }
+
+            let justified_width = ruby_box.size.x / ruby_box.scale * ruby_layout_info.scale_factor;
+            if justify_glyphs(
+                ruby_layout_info.bypass_change_detection(),
+                justified_width,
+                ruby.align,
+            ) {
+                ruby_layout_info.set_changed();
+            }
+
+            placed.push((rt_id, ruby_box));
+        }
+
+        let (text_scale, text_rotation, _) = text_global_transform.to_scale_rotation_translation();
+        for (rt_id, ruby_box) in placed {
+            if let Ok(mut overhang) = overhangs.get_mut(rt_id) {
+                overhang.set_if_neq(ruby_box.overhang());
+            }
+
+            let Ok((mut transform, child_of)) = ruby_transforms.get_mut(rt_id) else {
+                continue;
+            };
+
+            let ruby_pos = text_2d_point(top_left, ruby_box.center).extend(transform.translation.z);
+            let ruby_scale = Vec3::new(ruby_box.scale, ruby_box.scale, 1.0);
+
+            // Ruby text as a child of the base is placed in its local space, and otherwise follows
+            // the base's global transform (e.g. an animated zoom)
+            let (ruby_pos, ruby_rotation, ruby_scale) =
+                if child_of.is_some_and(|c| c.parent() == text_root_id) {
+                    (ruby_pos, Quat::IDENTITY, ruby_scale)
+                } else {
+                    (
+                        text_global_transform.transform_point(ruby_pos),
+                        text_rotation,
+                        text_scale * ruby_scale,
+                    )
+                };
+
+            if transform.translation == ruby_pos
+                && transform.rotation == ruby_rotation
+                && transform.scale == ruby_scale
+            {
+                continue;
+            }
+            transform.translation = ruby_pos;
+            transform.rotation = ruby_rotation;
+            transform.scale = ruby_scale;
+        }
+        if !hidden {
+            placed_roots.placed(text_root_id, ruby_count);
+        }
+    }
+    section_rects.retain_used();
+    placed_roots.finish_run();
+}
+
+/// Lays out `Text2d` (including ruby text synced with its [`Ruby`]) right away, running the systems
+/// Bevy runs for it in `PostUpdate`.
+///
+/// Systems whose resources are missing (e.g. without `TextPlugin`) are skipped.
+pub(crate) fn lay_out_text_2d(world: &mut World) {
+    let _ = world.run_system_cached(update_ruby_text_2d);
+    let _ = world.run_system_cached(detect_text_needs_rerender::<Text2d>);
+    let _ = world.run_system_cached(update_text2d_layout);
+}
+
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn ruby_placement_2d(
+    In(base): In<Entity>,
+    ruby_query: Query<(
+        Entity,
+        &Ruby,
+        &LinkedRubyText2d,
+        TextRootEntity,
+        Option<&MergedRuby>,
+    )>,
+    outer_annotations: OuterAnnotations,
+    text_layouts: Query<(&TextLayoutInfo, &Visibility)>,
+    text_2d_transforms: Query<(&GlobalTransform, &TextBounds, &Anchor), With<Text2d>>,
+    ancestors: Query<&ChildOf>,
+    disabled: Query<(), With<FuriganaDisabled>>,
+    visibilities: Query<&Visibility, Without<TextLayoutInfo>>,
+    mut text_reader: Text2dReader,
+    text_fonts: Query<&TextFont>,
+    fonts: Res<Assets<Font>>,
+    settings: Res<FuriganaSettings>,
+) -> Option<RubyPlacement> {
+    let text_root_id = ruby_query.get(base).ok()?.3.get()?;
+    let (layout_info, _) = text_layouts.get(text_root_id).ok()?;
+    let (text_global_transform, bounds, anchor) = text_2d_transforms.get(text_root_id).ok()?;
+    let scale_factor = layout_info.scale_factor;
+    if root_ruby_hidden(text_root_id, &ancestors, &disabled, |entity| {
+        text_layouts
+            .get(entity)
+            .map(|(_, visibility)| *visibility)
+            .or_else(|_| visibilities.get(entity).copied())
+            .ok()
+    }) {
+        return None;
+    }
+
+    // All rubies of the text root, in the same order as in `update_ruby_2d`, since they are placed
+    // against each other
+    let outer_annotations =
+        outer_annotations
+            .iter()
+            .map(|(text_entity, _, ruby, rt_id, text_root)| {
+                (text_entity, ruby, rt_id, text_root, None)
+            });
+    let rubies = ruby_query
+        .iter()
+        .map(|(text_entity, ruby, linked, text_root, merged)| {
+            (text_entity, ruby, linked.entity(), text_root.get(), merged)
+        })
+        .chain(outer_annotations)
+        .filter(|&(_, _, _, text_root, _)| text_root == Some(text_root_id))
+        .map(|(text_entity, ruby, rt_id, _, merged)| {
+            let ruby_size = text_layouts
+                .get(rt_id)
+                .ok()
+                .map(|(ruby_layout_info, _)| ruby_layout_info.size * scale_factor);
+            (text_entity, ruby, merged, ruby_size)
+        })
+        .collect::<Vec<_>>();
+    // Rubies come before other annotations, so this is the ruby rather than another annotation of
+    // `base`
+    let index = rubies
+        .iter()
+        .position(|&(text_entity, _, _, _)| text_entity == base)?;
+
+    let lines = text_lines(
+        text_reader
+            .iter(text_root_id)
+            .map(|(_, _, text, _, _)| text),
+    );
+    let non_cjk = non_cjk_sections(
+        text_reader
+            .iter(text_root_id)
+            .map(|(entity, _, text, _, _)| (entity, text)),
+        &settings,
+    );
+    let block = TextBlock::new(layout_info, &lines);
+    let ruby_box = place_block_rubies(&block, &rubies, &non_cjk, &text_fonts, &fonts, &settings)
+        .swap_remove(index)?;
+
+    let ruby_pos = text_2d_point(
+        text_2d_top_left(layout_info, bounds, anchor),
+        ruby_box.to_logical(scale_factor).center,
+    )
+    .extend(0.0);
+
+    Some(RubyPlacement::new(
+        ruby_box.base_rect,
+        ruby_box,
+        scale_factor,
+        text_global_transform.transform_point(ruby_pos),
+    ))
+}
+
+/// Window rect of the annotation text of `base` in slot `S`.
+#[expect(clippy::type_complexity)]
+pub(crate) fn ruby_window_rect_2d<S: RubySlot2d>(
+    In(base): In<Entity>,
+    linked: Query<&S::Linked>,
+    ruby_text: Query<
+        (&TextLayoutInfo, &GlobalTransform, &Visibility),
+        Or<(
+            With<RubyText2d>,
+            With<RubyTranslationText>,
+            With<RubyStackText>,
+        )>,
+    >,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) -> Option<Rect> {
+    // The first annotation text of a slot with several of them
+    let rt_id = linked.get(base).ok()?.iter().next()?;
+    let (layout_info, transform, visibility) = ruby_text.get(rt_id).ok()?;
+    if *visibility == Visibility::Hidden {
+        return None;
+    }
+
+    let (camera, camera_transform) = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .max_by_key(|(camera, _)| camera.order)?;
+    let viewport_min = camera.logical_viewport_rect()?.min;
+
+    let half_size = layout_info.size / 2.0;
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .map(|(x, y)| {
+            let corner = transform.transform_point((half_size * Vec2::new(x, y)).extend(0.0));
+            camera.world_to_viewport(camera_transform, corner).ok()
+        })
+        .into_iter()
+        .collect::<Option<Vec<_>>>()?;
+    let rect = bounding_rect(corners);
+    Some(Rect::from_corners(
+        rect.min + viewport_min,
+        rect.max + viewport_min,
+    ))
+}
+
+/// Top-left corner of the text block of a `Text2d` relative to its transform, the same way Bevy
+/// renders it.
+///
+/// Justified text is aligned within `bounds` rather than the laid out size, so the bounds must be
+/// taken into account when set.
+fn text_2d_top_left(layout_info: &TextLayoutInfo, bounds: &TextBounds, anchor: &Anchor) -> Vec2 {
+    let size = Vec2::new(
+        bounds.width.unwrap_or(layout_info.size.x),
+        bounds.height.unwrap_or(layout_info.size.y),
+    );
+    (Anchor::TOP_LEFT.as_vec() - anchor.as_vec()) * size
+}
+
+/// Converts a logical point in the text layout (Y+ down) to the local space of the `Text2d`
+/// (Y+ up).
+fn text_2d_point(top_left: Vec2, point: Vec2) -> Vec2 {
+    top_left + Vec2::new(point.x, -point.y)
+}
 
-        let Ok(mut transform) = ruby_transforms.get_mut(rt_id) else {
+/// Spawns, syncs and despawns the copies of 2D ruby text making up its outline, as its children.
+#[expect(clippy::type_complexity)]
+fn update_ruby_outline_2d(
+    ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText2d, &RubyTranslationText, &RubyStackText)>,
+            Ref<Text2d>,
+            Ref<TextFont>,
+            Ref<TextColor>,
+            Option<&Children>,
+        ),
+        Without<RubyOutline2d>,
+    >,
+    rubies: Query<(
+        Option<Ref<Ruby>>,
+        Option<Ref<RubyTranslation>>,
+        Option<Ref<RubyStack>>,
+    )>,
+    outline_copies: Query<&RubyOutline2d>,
+    mut commands: Commands,
+) {
+    for (rt_entity, link, text, font, color, children) in &ruby_text {
+        let Some((src_id, annotation)) = annotation_of(link) else {
+            continue;
+        };
+        let Ok((reading, translation, stack)) = rubies.get(src_id) else {
+            continue;
+        };
+        let Some((ruby_changed, ruby)) = annotation_ruby(annotation, reading, translation, stack)
+        else {
             continue;
         };
+        let outline = ruby.outline;
 
-        let mut ruby_pos =
-            ruby_pos_local.extend(transform.translation.z) - text_layout_size.extend(0.0) / 2.0;
-        // Y+ down to Y+ up
-        ruby_pos.y = -ruby_pos.y;
+        let copies = children
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| Some((child, outline_copies.get(child).ok()?.direction)))
+            .collect::<Vec<_>>();
 
-        let Ok(text_global_transform) = text_2d_transforms.get(text_root_id) else {
+        let Some(outline) = outline else {
+            for (copy_id, _) in copies {
+                commands.entity(copy_id).despawn();
+            }
             continue;
         };
 
-        let ruby_pos_global = text_global_transform.transform_point(ruby_pos);
+        let copy = |direction| {
+            // Y up in 2D
+            let offset = outline.offset(direction, font.font_size) * Vec2::new(1.0, -1.0);
+            (
+                Text2d(text.0.clone()),
+                font.clone(),
+                outline.text_color(*color),
+                TextLayout::new_with_no_wrap(),
+                // Behind the ruby text, but in front of its background
+                Transform::from_translation(offset.extend(-0.0005)),
+            )
+        };
+
+        if copies.is_empty() {
+            for direction in RubyOutline::DIRECTIONS {
+                commands.spawn((
+                    RubyOutline2d { direction },
+                    copy(direction),
+                    ChildOf(rt_entity),
+                ));
+            }
+        } else if ruby_changed || text.is_changed() || font.is_changed() || color.is_changed() {
+            for (copy_id, direction) in copies {
+                commands.entity(copy_id).insert(copy(direction));
+            }
+        }
+    }
+}
 
-        let ruby_rotation = text_global_transform.to_scale_rotation_translation().1;
+#[expect(clippy::type_complexity)]
+fn update_ruby_background_2d(
+    ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText2d, &RubyTranslationText, &RubyStackText)>,
+            &TextLayoutInfo,
+            Option<&Children>,
+        ),
+        With<Text2d>,
+    >,
+    backgrounds: Query<&TextBackgroundColor>,
+    mut sprites: Query<&mut Sprite, With<RubyBackground2d>>,
+    settings: Res<FuriganaSettings>,
+    mut commands: Commands,
+) {
+    for (rt_entity, link, layout_info, children) in &ruby_text {
+        let Some((ruby_id, _)) = annotation_of(link) else {
+            continue;
+        };
+        let background_id =
+            children.and_then(|children| children.iter().find(|&child| sprites.contains(child)));
 
-        if transform.translation == ruby_pos_global && transform.rotation == ruby_rotation {
+        let Some((padding, &TextBackgroundColor(color))) = settings
+            .ruby_background_padding
+            .zip(backgrounds.get(ruby_id).ok())
+        else {
+            if let Some(background_id) = background_id {
+                commands.entity(background_id).despawn();
+            }
             continue;
+        };
+
+        let size = layout_info.size + 2.0 * padding;
+        if let Some(mut sprite) = background_id.and_then(|id| sprites.get_mut(id).ok()) {
+            if sprite.color != color || sprite.custom_size != Some(size) {
+                sprite.color = color;
+                sprite.custom_size = Some(size);
+            }
+        } else {
+            commands.spawn((
+                RubyBackground2d,
+                Sprite::from_color(color, size),
+                // Just behind the ruby text
+                Transform::from_xyz(0.0, 0.0, -0.001),
+                ChildOf(rt_entity),
+            ));
         }
-        transform.translation = ruby_pos_global;
-        transform.rotation = ruby_rotation;
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::any::TypeId;
+
+    use bevy::{
+        camera::{ComputedCameraValues, RenderTargetInfo, visibility::VisibleEntities},
+        ecs::system::RunSystemOnce,
+        text::{
+            CosmicFontSystem, DEFAULT_FONT_DATA, FontAtlasSets, LineHeight, SwashCache,
+            TextIterScratch, TextPipeline,
+        },
+    };
+
     use super::*;
+    use crate::{RubyAnchor, RubyShadow};
+
+    /// Sets up the real text pipeline with the default font, and a camera to lay out `Text2d` for.
+    fn init_text_pipeline_2d(world: &mut World) {
+        world.init_resource::<Assets<Font>>();
+        world.init_resource::<Assets<Image>>();
+        world.init_resource::<Assets<TextureAtlasLayout>>();
+        world.init_resource::<FontAtlasSets>();
+        world.init_resource::<TextPipeline>();
+        world.init_resource::<CosmicFontSystem>();
+        world.init_resource::<SwashCache>();
+        world.init_resource::<TextIterScratch>();
+        world
+            .resource_mut::<Assets<Font>>()
+            .insert(
+                AssetId::default(),
+                Font::try_from_bytes(DEFAULT_FONT_DATA.to_vec()).unwrap(),
+            )
+            .unwrap();
+
+        let mut visible_entities = VisibleEntities::default();
+        visible_entities.push(Entity::PLACEHOLDER, TypeId::of::<Sprite>());
+        world.spawn((
+            Camera {
+                computed: ComputedCameraValues {
+                    target_info: Some(RenderTargetInfo {
+                        physical_size: UVec2::splat(1000),
+                        scale_factor: 1.0,
+                    }),
+                    ..default()
+                },
+                ..default()
+            },
+            visible_entities,
+        ));
+    }
+
+    /// Lays out a `Text2d` annotated with `ruby` using the real text pipeline and the default font,
+    /// then places its ruby text. Returns the text and ruby text entities.
+    fn lay_out_ruby_2d(app: &mut App, text_font: TextFont, ruby: Ruby) -> (Entity, Entity) {
+        let world = app.world_mut();
+        init_text_pipeline_2d(world);
+
+        let text_entity = world.spawn((Text2d::new("Hex"), text_font, ruby)).id();
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+
+        world
+            .run_system_once(detect_text_needs_rerender::<Text2d>)
+            .unwrap();
+        world.run_system_once(update_text2d_layout).unwrap();
+        world.run_system_once(update_ruby_2d).unwrap();
+
+        (text_entity, rt_id)
+    }
+
+    /// Y coordinate of the baseline of the first glyph of `layout_info`, in the local space of its
+    /// `Text2d` (Y+ up).
+    fn baseline_2d(layout_info: &TextLayoutInfo) -> f32 {
+        let glyph = &layout_info.glyphs[0];
+        let baseline =
+            glyph.position.y - glyph.size.y / 2.0 + glyph.atlas_info.location.offset.y as f32;
+        layout_info.size.y / 2.0 - baseline / layout_info.scale_factor
+    }
 
     #[test]
     fn test_add_ruby_creates_ruby_text_2d() {
@@ -271,4 +1141,720 @@ mod tests {
                 .is_none()
         );
     }
+
+    #[test]
+    fn test_removing_text_2d_despawns_ruby_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text2d::new("犬"), Ruby::new("いぬ"))).id();
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+
+        world.entity_mut(text_entity).remove::<Text2d>();
+        world.flush();
+
+        assert!(world.get_entity(rt_id).is_err());
+        assert!(world.get::<Ruby>(text_entity).is_some());
+    }
+
+    #[test]
+    fn test_removing_ruby_despawns_ruby_text_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text2d::new("犬"), Ruby::new("いぬ"))).id();
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+
+        world.entity_mut(text_entity).remove::<Ruby>();
+        world.flush();
+
+        assert!(world.get_entity(rt_id).is_err());
+        assert!(world.get::<Text2d>(text_entity).is_some());
+    }
+
+    #[test]
+    fn test_ruby_shadow_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let shadow = RubyShadow {
+            offset: Vec2::new(1.0, 2.0),
+            color: Color::WHITE,
+        };
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text2d::new("text"),
+                Ruby {
+                    shadow: Some(shadow),
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        // Same direction on screen as for UI
+        let rt_shadow = app.world().get::<Text2dShadow>(rt_id).unwrap();
+        assert_eq!(
+            (rt_shadow.offset, rt_shadow.color),
+            (Vec2::new(1.0, -2.0), Color::WHITE)
+        );
+
+        app.world_mut().get_mut::<Ruby>(text_entity).unwrap().shadow = None;
+        app.world_mut()
+            .run_system_once(update_ruby_text_2d)
+            .unwrap();
+        assert!(app.world().get::<Text2dShadow>(rt_id).is_none());
+    }
+
+    #[test]
+    fn test_clearing_ruby_color_reverts_to_base_color_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let red = Color::srgb(1.0, 0.0, 0.0);
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text2d::new("text"),
+                TextColor(red),
+                Ruby {
+                    color: Some(TextColor(Color::srgb(0.0, 0.0, 1.0))),
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+
+        app.world_mut().get_mut::<Ruby>(text_entity).unwrap().color = None;
+        app.world_mut()
+            .run_system_once(update_ruby_text_2d)
+            .unwrap();
+        assert_eq!(app.world().get::<TextColor>(rt_id).unwrap().0, red);
+    }
+
+    #[test]
+    fn test_ruby_background_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .insert_resource(FuriganaSettings {
+                ruby_background_padding: Some(Vec2::splat(2.0)),
+                ..default()
+            });
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Ruby::new("ruby"),
+                Text2d::new("text"),
+                TextBackgroundColor(Color::WHITE),
+            ))
+            .id();
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(rt_id)
+            .unwrap()
+            .size = Vec2::new(40.0, 10.0);
+
+        app.world_mut()
+            .run_system_once(update_ruby_background_2d)
+            .unwrap();
+        app.world_mut().flush();
+
+        let world = app.world_mut();
+        let (sprite, &ChildOf(parent)) = world
+            .query_filtered::<(&Sprite, &ChildOf), With<RubyBackground2d>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(parent, rt_id);
+        assert_eq!(sprite.color, Color::WHITE);
+        assert_eq!(sprite.custom_size, Some(Vec2::new(44.0, 14.0)));
+
+        world
+            .entity_mut(text_entity)
+            .remove::<TextBackgroundColor>();
+        world.run_system_once(update_ruby_background_2d).unwrap();
+        world.flush();
+        assert_eq!(
+            world
+                .query_filtered::<(), With<RubyBackground2d>>()
+                .iter(world)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_ruby_2d_on_justified_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        // Right-justified within 200px bounds, while the laid out size is only as wide as the text
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text2d::new("漢字"),
+                Ruby::new("かんじ"),
+                TextLayout::new_with_justify(Justify::Right),
+                TextBounds::new_horizontal(200.0),
+            ))
+            .id();
+        app.world_mut()
+            .entity_mut(text_entity)
+            .insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![(text_entity, Rect::new(160.0, 0.0, 200.0, 20.0))],
+                size: Vec2::new(40.0, 20.0),
+            });
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(rt_id)
+            .unwrap()
+            .size = Vec2::new(30.0, 10.0);
+
+        app.world_mut().run_system_once(update_ruby_2d).unwrap();
+
+        // Block spans -100..100 horizontally and -10..10 vertically
+        let transform = app.world().get::<Transform>(rt_id).unwrap();
+        assert_eq!(transform.translation.truncate(), Vec2::new(80.0, 10.0));
+    }
+
+    #[test]
+    fn test_ruby_stack_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text2d::new("漢字"),
+                Ruby::new("かんじ"),
+                RubyStack(vec![Ruby::new("x"), Ruby::new("y").under()]),
+            ))
+            .id();
+        app.world_mut()
+            .entity_mut(text_entity)
+            .insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![(text_entity, Rect::new(0.0, 0.0, 40.0, 20.0))],
+                size: Vec2::new(40.0, 20.0),
+            });
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        let stack_ids = app
+            .world_mut()
+            .run_system_once(move |links: crate::RubyLinks| {
+                [0, 1].map(|index| links.stack_text(text_entity, index).unwrap())
+            })
+            .unwrap();
+        for id in core::iter::once(rt_id).chain(stack_ids) {
+            app.world_mut().get_mut::<TextLayoutInfo>(id).unwrap().size = Vec2::new(30.0, 10.0);
+        }
+
+        app.world_mut().run_system_once(update_ruby_2d).unwrap();
+
+        assert_eq!(app.world().get::<Text2d>(stack_ids[1]).unwrap().0, "y");
+        // Block spans -10..10 vertically, and the annotation over it is stacked over the reading
+        let y = |id| app.world().get::<Transform>(id).unwrap().translation.y;
+        assert_eq!(y(rt_id), 10.0);
+        assert_eq!(y(stack_ids[0]), 20.0);
+        assert_eq!(y(stack_ids[1]), -10.0);
+    }
+
+    #[test]
+    fn test_ruby_2d_on_lone_span() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let world = app.world_mut();
+        let root_base = world.spawn((Text2d::new("漢字"), Ruby::new("かんじ"))).id();
+        let span_root = world.spawn(Text2d::default()).id();
+        let span_base = world
+            .spawn((
+                TextSpan::new("漢字"),
+                Ruby::new("かんじ"),
+                ChildOf(span_root),
+            ))
+            .id();
+
+        // The empty root text takes no space before the span
+        let base_rect = Rect::new(0.0, 0.0, 40.0, 20.0);
+        for (text_root, section_rects) in [
+            (root_base, vec![(root_base, base_rect)]),
+            (
+                span_root,
+                vec![
+                    (span_root, Rect::new(0.0, 0.0, 0.0, 20.0)),
+                    (span_base, base_rect),
+                ],
+            ),
+        ] {
+            world.entity_mut(text_root).insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects,
+                size: Vec2::new(40.0, 20.0),
+            });
+        }
+        let rt_ids = [root_base, span_base]
+            .map(|base| world.get::<LinkedRubyText2d>(base).unwrap().entity());
+        for rt_id in rt_ids {
+            world.get_mut::<TextLayoutInfo>(rt_id).unwrap().size = Vec2::new(30.0, 10.0);
+        }
+
+        world.run_system_once(update_ruby_2d).unwrap();
+
+        // Centered over the whole base, the same as ruby on the root
+        for rt_id in rt_ids {
+            let transform = world.get::<Transform>(rt_id).unwrap();
+            assert_eq!(transform.translation.truncate(), Vec2::new(0.0, 10.0));
+            assert_eq!(world.get::<Visibility>(rt_id), Some(&Visibility::Inherited));
+        }
+    }
+
+    #[test]
+    fn test_ruby_2d_on_span_among_other_children() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .insert_resource(FuriganaSettings {
+                ruby_2d_child_of_base: true,
+                ..default()
+            });
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let world = app.world_mut();
+        let text_root = world.spawn(Text2d::default()).id();
+        let sprite = world.spawn((Transform::default(), ChildOf(text_root))).id();
+        // Not part of the text, so its ruby is not shown
+        let stray_span = world
+            .spawn((TextSpan::new("猫"), Ruby::new("ねこ"), ChildOf(sprite)))
+            .id();
+        let span = world
+            .spawn((
+                TextSpan::new("漢字"),
+                Ruby::new("かんじ"),
+                ChildOf(text_root),
+            ))
+            .id();
+
+        assert!(world.get::<LinkedRubyText2d>(stray_span).is_none());
+        let rt_id = world.get::<LinkedRubyText2d>(span).unwrap().entity();
+        assert_eq!(world.get::<ChildOf>(rt_id).unwrap().parent(), text_root);
+
+        world.entity_mut(text_root).insert(TextLayoutInfo {
+            scale_factor: 1.0,
+            glyphs: vec![],
+            section_rects: vec![
+                (text_root, Rect::new(0.0, 0.0, 0.0, 20.0)),
+                (span, Rect::new(0.0, 0.0, 40.0, 20.0)),
+            ],
+            size: Vec2::new(40.0, 20.0),
+        });
+        world.get_mut::<TextLayoutInfo>(rt_id).unwrap().size = Vec2::new(30.0, 10.0);
+
+        world.run_system_once(update_ruby_2d).unwrap();
+
+        let transform = world.get::<Transform>(rt_id).unwrap();
+        assert_eq!(transform.translation.truncate(), Vec2::new(0.0, 10.0));
+        assert_eq!(world.get::<Visibility>(rt_id), Some(&Visibility::Inherited));
+    }
+
+    #[test]
+    fn test_ruby_2d_on_span_without_style() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_root = world
+            .spawn((
+                Text2d::default(),
+                TextFont::from_font_size(40.0),
+                TextColor(Color::BLACK),
+            ))
+            .id();
+        let span = world
+            .spawn((TextSpan::new("漢字"), Ruby::new("かんじ")))
+            .remove::<(TextFont, TextColor)>()
+            .id();
+        world.entity_mut(span).insert(ChildOf(text_root));
+
+        let rt_id = world.get::<LinkedRubyText2d>(span).unwrap().entity();
+        assert_eq!(world.get::<TextFont>(rt_id).unwrap().font_size, 20.0);
+        assert_eq!(world.get::<TextColor>(rt_id).unwrap().0, Color::BLACK);
+    }
+
+    #[test]
+    fn test_ruby_2d_follows_anchor_change() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text2d::new("漢字"), Ruby::new("かんじ"))).id();
+        world.entity_mut(text_entity).insert(TextLayoutInfo {
+            scale_factor: 1.0,
+            glyphs: vec![],
+            section_rects: vec![(text_entity, Rect::new(0.0, 0.0, 40.0, 20.0))],
+            size: Vec2::new(40.0, 20.0),
+        });
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+        world.get_mut::<TextLayoutInfo>(rt_id).unwrap().size = Vec2::new(30.0, 10.0);
+
+        for (anchor, ruby_translation) in [
+            (Anchor::CENTER, Vec2::new(0.0, 10.0)),
+            (Anchor::TOP_LEFT, Vec2::new(20.0, 0.0)),
+            (Anchor::BOTTOM_RIGHT, Vec2::new(-20.0, 20.0)),
+            (Anchor::CENTER, Vec2::new(0.0, 10.0)),
+        ] {
+            world.entity_mut(text_entity).insert(anchor);
+            world.run_system_once(update_ruby_2d).unwrap();
+
+            let transform = world.get::<Transform>(rt_id).unwrap();
+            assert_eq!(transform.translation.truncate(), ruby_translation);
+        }
+    }
+
+    #[test]
+    fn test_ruby_2d_on_bottom_left_anchored_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text2d::new("漢字"),
+                Ruby::new("かんじ"),
+                Anchor::BOTTOM_LEFT,
+                GlobalTransform::from_xyz(100.0, 50.0, 0.0),
+            ))
+            .id();
+        world.entity_mut(text_entity).insert(TextLayoutInfo {
+            scale_factor: 1.0,
+            glyphs: vec![],
+            section_rects: vec![(text_entity, Rect::new(0.0, 0.0, 40.0, 20.0))],
+            size: Vec2::new(40.0, 20.0),
+        });
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+        world.get_mut::<TextLayoutInfo>(rt_id).unwrap().size = Vec2::new(30.0, 10.0);
+
+        world.run_system_once(update_ruby_2d).unwrap();
+
+        // Block spans 100..140 horizontally and 50..70 vertically
+        let transform = world.get::<Transform>(rt_id).unwrap();
+        assert_eq!(transform.translation.truncate(), Vec2::new(120.0, 70.0));
+    }
+
+    #[test]
+    fn test_ruby_outline_2d_scales_with_font_size() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        for font_size_scale in [0.5, 1.0] {
+            let text_entity = world
+                .spawn((
+                    Text2d::new("犬"),
+                    TextFont::from_font_size(20.0),
+                    Ruby {
+                        font_size_scale,
+                        outline: Some(RubyOutline {
+                            color: Color::WHITE,
+                            width: 0.1,
+                        }),
+                        ..Ruby::new("いぬ")
+                    },
+                ))
+                .id();
+            let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+            world.run_system_once(update_ruby_outline_2d).unwrap();
+
+            let children = world.get::<Children>(rt_id).unwrap();
+            assert_eq!(children.len(), 8);
+            let offsets = children
+                .iter()
+                .filter(|&child| world.get::<RubyOutline2d>(child).unwrap().direction == Vec2::Y)
+                .map(|child| world.get::<Transform>(child).unwrap().translation)
+                .collect::<Vec<_>>();
+            // Down, with Y up in 2D
+            assert_eq!(offsets, [Vec3::new(0.0, -2.0 * font_size_scale, -0.0005)]);
+        }
+    }
+
+    #[test]
+    fn test_compute_placement_now_2d() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        init_text_pipeline_2d(world);
+
+        // Neither the text nor its ruby text is laid out yet
+        let text_entity = world
+            .spawn((
+                Text2d::new("Hex"),
+                TextFont::from_font_size(20.0),
+                Ruby::new("ruby"),
+            ))
+            .id();
+        let placement = crate::compute_placement_now(world, text_entity).unwrap();
+
+        // Where the ruby text is then placed
+        world.run_system_once(update_ruby_2d).unwrap();
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+        let translation = world.get::<Transform>(rt_id).unwrap().translation;
+        assert_eq!(translation.truncate(), placement.ruby_center.truncate());
+    }
+
+    #[test]
+    fn test_mono_ruby_2d_readings_over_their_characters() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        init_text_pipeline_2d(world);
+
+        let text_entity = world
+            .spawn((
+                Text2d::new("AB"),
+                TextFont::from_font_size(20.0),
+                Ruby::mono(["xy", "z"]),
+            ))
+            .id();
+        for _ in 0..3 {
+            lay_out_text_2d(world);
+            world.run_system_once(update_ruby_2d).unwrap();
+        }
+
+        // Both are centered on their translation, and the ruby text is a child of the base
+        let layout_info = world.get::<TextLayoutInfo>(text_entity).unwrap();
+        let base_rect = layout_info.section_rects[0].1;
+        let base_left = -layout_info.size.x / 2.0;
+        let rt_id = world.get::<LinkedRubyText2d>(text_entity).unwrap().entity();
+        let rt_layout_info = world.get::<TextLayoutInfo>(rt_id).unwrap();
+        let rt_left =
+            world.get::<Transform>(rt_id).unwrap().translation.x - rt_layout_info.size.x / 2.0;
+
+        // The default font is monospace, so each character of the base takes half of it
+        let char_width = base_rect.width() / 2.0;
+        for (index, bytes) in [0..2, 2..3].into_iter().enumerate() {
+            let (left, right) = rt_layout_info
+                .glyphs
+                .iter()
+                .filter(|glyph| bytes.contains(&glyph.byte_index))
+                .map(|glyph| {
+                    let half_width = glyph.size.x / 2.0;
+                    (glyph.position.x - half_width, glyph.position.x + half_width)
+                })
+                .reduce(|(a, b), (c, d)| (a.min(c), b.max(d)))
+                .unwrap();
+            let reading_center = rt_left + f32::midpoint(left, right);
+            let char_center = base_left + base_rect.min.x + char_width * (index as f32 + 0.5);
+            assert!(
+                (reading_center - char_center).abs() < 0.5,
+                "reading {index} at {reading_center} != {char_center}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ruby_2d_child_of_base() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .insert_resource(FuriganaSettings {
+                ruby_2d_child_of_base: true,
+                ..default()
+            });
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text2d::new("漢字"),
+                Ruby::new("かんじ"),
+                GlobalTransform::from_xyz(50.0, 0.0, 0.0),
+            ))
+            .id();
+        app.world_mut()
+            .entity_mut(text_entity)
+            .insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![(text_entity, Rect::new(0.0, 0.0, 40.0, 20.0))],
+                size: Vec2::new(40.0, 20.0),
+            });
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        assert_eq!(
+            app.world().get::<ChildOf>(rt_id).unwrap().parent(),
+            text_entity
+        );
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(rt_id)
+            .unwrap()
+            .size = Vec2::new(30.0, 10.0);
+
+        app.world_mut().run_system_once(update_ruby_2d).unwrap();
+
+        // Local to the base, regardless of its global transform
+        let transform = app.world().get::<Transform>(rt_id).unwrap();
+        assert_eq!(transform.translation, Vec3::new(0.0, 10.0, 0.01));
+    }
+
+    #[test]
+    fn test_ruby_2d_follows_scaled_base() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.world_mut().init_resource::<Assets<Font>>();
+
+        let text_entity = app
+            .world_mut()
+            .spawn((Text2d::new("漢字"), Ruby::new("かんじ")))
+            .id();
+        app.world_mut()
+            .entity_mut(text_entity)
+            .insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![(text_entity, Rect::new(0.0, 0.0, 40.0, 20.0))],
+                size: Vec2::new(40.0, 20.0),
+            });
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText2d>(text_entity)
+            .unwrap()
+            .entity();
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(rt_id)
+            .unwrap()
+            .size = Vec2::new(30.0, 10.0);
+
+        // Zooming in over frames
+        for zoom in [1.0, 1.5, 2.0] {
+            app.world_mut()
+                .entity_mut(text_entity)
+                .insert(GlobalTransform::from_scale(Vec3::splat(zoom)));
+            app.world_mut().run_system_once(update_ruby_2d).unwrap();
+
+            let transform = app.world().get::<Transform>(rt_id).unwrap();
+            assert_eq!(transform.scale, Vec3::splat(zoom));
+            assert_eq!(
+                transform.translation.truncate(),
+                Vec2::new(0.0, 10.0 * zoom)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ruby_2d_across_line_heights() {
+        let font_size = 20.0;
+        let mut cap_gaps = vec![];
+        for (line_height, line_box_height) in [
+            // `RelativeToFont(1.2)`
+            (LineHeight::default(), 24.0),
+            (LineHeight::Px(50.0), 50.0),
+            (LineHeight::RelativeToFont(2.0), 40.0),
+        ] {
+            let text_font = TextFont {
+                font_size,
+                line_height,
+                ..default()
+            };
+            let place = |anchor| {
+                let mut app = App::new();
+                app.add_plugins(crate::FuriganaPlugin);
+                let ruby = Ruby {
+                    anchor,
+                    ..Ruby::new("ka")
+                };
+                let (text_entity, rt_id) = lay_out_ruby_2d(&mut app, text_font.clone(), ruby);
+                let world = app.world();
+                (
+                    world.get::<TextLayoutInfo>(text_entity).unwrap().clone(),
+                    world.get::<TextLayoutInfo>(rt_id).unwrap().size,
+                    *world.get::<Transform>(rt_id).unwrap(),
+                )
+            };
+
+            // The line box follows the line height, while ruby text is sized by the font size
+            let (layout_info, ruby_size, transform) = place(RubyAnchor::LineBox);
+            let line_box = layout_info.section_rects[0].1;
+            assert!((line_box.height() - line_box_height).abs() < 0.01);
+            assert!(ruby_size.y > 0.0 && ruby_size.y < line_box.height());
+            assert_eq!(transform.scale, Vec3::ONE);
+
+            // Ruby is centered on the top of the line box
+            assert!((transform.translation.y - layout_info.size.y / 2.0).abs() < 0.01);
+
+            // Anchored to the cap height, ruby keeps the same distance from the baseline
+            let (layout_info, ruby_size, transform) = place(RubyAnchor::CapHeight);
+            let ruby_bottom = transform.translation.y - ruby_size.y / 2.0;
+            cap_gaps.push(ruby_bottom - baseline_2d(&layout_info));
+        }
+
+        assert!(cap_gaps[0] > 0.0);
+        for gap in &cap_gaps[1..] {
+            assert!((gap - cap_gaps[0]).abs() < 1.0, "{cap_gaps:?}");
+        }
+    }
+
+    #[test]
+    fn test_ruby_2d_follows_animated_font_size() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+        let (text_entity, rt_id) = lay_out_ruby_2d(&mut app, TextFont::default(), Ruby::new("ka"));
+
+        let world = app.world_mut();
+        let mut ruby_heights = vec![];
+        for font_size in [24.0, 28.0, 32.0, 28.0] {
+            world.get_mut::<TextFont>(text_entity).unwrap().font_size = font_size;
+            world.run_system_once(update_ruby_text_2d).unwrap();
+            world
+                .run_system_once(detect_text_needs_rerender::<Text2d>)
+                .unwrap();
+            world.run_system_once(update_text2d_layout).unwrap();
+            world.run_system_once(update_ruby_2d).unwrap();
+
+            assert_eq!(
+                world.get::<TextFont>(rt_id).unwrap().font_size,
+                font_size / 2.0
+            );
+            // Still centered on the top of the growing (or shrinking) line box
+            let base_height = world.get::<TextLayoutInfo>(text_entity).unwrap().size.y;
+            let ruby_y = world.get::<Transform>(rt_id).unwrap().translation.y;
+            assert!((ruby_y - base_height / 2.0).abs() < 0.01);
+            ruby_heights.push(world.get::<TextLayoutInfo>(rt_id).unwrap().size.y);
+        }
+        assert!(ruby_heights[0] < ruby_heights[1] && ruby_heights[1] < ruby_heights[2]);
+        assert_eq!(ruby_heights[3], ruby_heights[1]);
+    }
 }
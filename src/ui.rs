@@ -1,21 +1,69 @@
-use bevy::{math::Affine2, prelude::*, text::TextLayoutInfo, ui::UiSystems};
+use bevy::{
+    app::{
+        propagate_inherited, propagate_output, update_reparented, update_source, update_stopped,
+    },
+    ecs::{
+        entity::{EntityHashMap, EntityHashSet},
+        relationship::RelationshipTarget,
+    },
+    math::Affine2,
+    prelude::*,
+    text::{TextLayoutInfo, detect_text_needs_rerender},
+    ui::{
+        ComputedUiRenderTargetInfo, ComputedUiTargetCamera, UiSystems, ui_layout_system,
+        update::propagate_ui_target_cameras,
+        widget::{TextUiReader, measure_text_system, text_system},
+    },
+};
 
-use crate::{FuriganaSettings, Ruby, RubyAlign, RubyPosition, TextRootEntity};
+use crate::{
+    Annotation, AnyRubyText, FuriganaDisabled, FuriganaSettings, FuriganaSystems,
+    LinkedRubyStackTexts, LinkedRubyTranslationText, NoRuby, OuterAnnotations, PlacedRoots, Ruby,
+    RubyFadeIn, RubyOutline, RubyOverhang, RubyPalette, RubyPlacement, RubySlot, RubyStack,
+    RubyStackText, RubyTranslation, RubyTranslationText, TextRootEntity, annotation_of,
+    annotation_ruby, center_mono_ruby,
+    layout::{SectionRectsCache, TextBlock, justify_glyphs, text_lines},
+    linked_annotation_texts,
+    merge::{MergeableSpan, MergedRuby, RubyMergedInto, merge_leader, merged_display_text},
+    mono_ruby_base_centers, non_cjk_sections, place_block_rubies,
+    placement::{RubyPositioned, RubySettling, RubyStabilized, RubyTextSpawned, bounding_rect},
+    reveal::{RubyReveal, revealed_text},
+    root_ruby_hidden, ruby_budget_exceeded, ruby_text_color, ruby_text_font,
+};
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(PostUpdate, update_ruby.after(UiSystems::Layout))
-        .add_systems(
-            PostUpdate,
-            (update_ruby_text, update_ruby_display).before(UiSystems::Content),
-        )
-        .add_observer(add_ruby)
-        .add_observer(add_ruby_text_span);
+    // After `UiSystems::PostLayout`, where ruby text is laid out, so that justified glyphs stick
+    app.add_systems(
+        PostUpdate,
+        (update_ruby, track_ruby_settling, update_ruby_outline)
+            .chain()
+            .after(UiSystems::PostLayout)
+            .in_set(FuriganaSystems::Update),
+    )
+    .add_systems(
+        PostUpdate,
+        (update_ruby_text, update_ruby_display)
+            .before(UiSystems::Content)
+            .in_set(FuriganaSystems::Spawn),
+    )
+    .add_observer(add_ruby::<Ruby>)
+    .add_observer(add_ruby::<RubyTranslation>)
+    .add_observer(add_ruby::<RubyStack>)
+    .add_observer(add_ruby_text_span::<Ruby>)
+    .add_observer(add_ruby_text_span::<RubyTranslation>)
+    .add_observer(add_ruby_text_span::<RubyStack>)
+    .add_observer(remove_ruby_text)
+    .add_observer(remove_slot_ruby_text::<Ruby>)
+    .add_observer(remove_slot_ruby_text::<RubyTranslation>)
+    .add_observer(remove_slot_ruby_text::<RubyStack>)
+    .add_observer(reparent_ruby_text)
+    .add_observer(unparent_ruby_text);
 }
 
 /// Component for UI ruby text.
 /// Automatically spawned when [`Ruby`] component is added along with `Text` or `TextSpan`.
 #[derive(Component, Clone, Copy)]
-#[require(Node)]
+#[require(Node, RubyOverhang, RubySettling)]
 #[relationship(relationship_target = LinkedRubyText)]
 pub struct RubyText(
     /// Entity of the corresponding `Ruby` component.
@@ -33,35 +81,205 @@ impl LinkedRubyText {
     }
 }
 
-pub fn add_ruby(
-    on: On<Add, Ruby>,
-    ruby_ui: Query<(&Ruby, &TextFont, Option<&ChildOf>, &ZIndex, &TextColor), With<Text>>,
-    commands: Commands,
+/// [`RubySlot`] on UI text.
+pub(crate) trait UiRubySlot: RubySlot {
+    /// Relationship target on the base entity, linking to the annotation texts.
+    type Linked: RelationshipTarget;
+
+    /// Links the annotation text of the `index`-th annotation of the slot to the base entity.
+    fn link(base: Entity, index: usize) -> impl Bundle;
+}
+
+impl UiRubySlot for Ruby {
+    type Linked = LinkedRubyText;
+
+    fn link(base: Entity, _index: usize) -> impl Bundle {
+        RubyText(base)
+    }
+}
+
+impl UiRubySlot for RubyTranslation {
+    type Linked = LinkedRubyTranslationText;
+
+    fn link(base: Entity, _index: usize) -> impl Bundle {
+        RubyTranslationText(base)
+    }
+}
+
+impl UiRubySlot for RubyStack {
+    type Linked = LinkedRubyStackTexts;
+
+    fn link(base: Entity, index: usize) -> impl Bundle {
+        RubyStackText { base, index }
+    }
+}
+
+/// Spawns the ruby text of a `Text` with `S`, whichever of them is added last (e.g. when loaded
+/// from a scene one component at a time).
+#[expect(clippy::type_complexity)]
+pub(crate) fn add_ruby<S: UiRubySlot>(
+    on: On<Add, (S, Text)>,
+    ruby_ui: Query<
+        (&S, &TextFont, Option<&ChildOf>, &ZIndex, &TextColor),
+        (With<Text>, Without<NoRuby>, Without<S::Linked>),
+    >,
+    settings: Res<FuriganaSettings>,
+    ruby_texts: Query<(), AnyRubyText>,
+    mut commands: Commands,
 ) {
-    if let Ok((ruby, text_font, child_of, &z_index, text_color)) = ruby_ui.get(on.entity) {
+    if let Ok((slot, text_font, child_of, &z_index, text_color)) = ruby_ui.get(on.entity) {
+        let ruby_text_count = ruby_texts.iter().count();
         let parent = child_of.map(ChildOf::parent);
-        create_ruby_text(
-            on,
-            commands,
-            parent,
-            ruby,
-            text_font,
-            ruby.font_size_scale,
-            z_index,
-            *text_color,
-        );
+        for (index, ruby) in slot.rubies().iter().enumerate() {
+            if ruby_budget_exceeded(&settings, ruby_text_count + index) {
+                return;
+            }
+
+            create_ruby_text::<S>(
+                on.entity,
+                index,
+                &mut commands,
+                parent,
+                ruby,
+                text_font,
+                &settings,
+                z_index,
+                *text_color,
+            );
+        }
+    }
+}
+
+/// Annotation texts linked to a UI base; see [`linked_annotation_texts`].
+type LinkedAnnotationTexts = (
+    Option<&'static LinkedRubyText>,
+    Option<&'static LinkedRubyTranslationText>,
+    Option<&'static LinkedRubyStackTexts>,
+);
+
+/// Despawns the ruby text of a base whose `Text` is removed, as it can no longer be placed.
+fn remove_ruby_text(
+    on: On<Remove, Text>,
+    linked: Query<LinkedAnnotationTexts>,
+    mut commands: Commands,
+) {
+    if let Ok(linked) = linked.get(on.entity) {
+        for rt_id in linked_annotation_texts(linked) {
+            // Already despawned along with the base, if it is being despawned
+            commands.entity(rt_id).try_despawn();
+        }
+    }
+}
+
+/// Despawns the annotation text of a base whose `S` is removed, so that no stale ruby is left.
+pub(crate) fn remove_slot_ruby_text<S: UiRubySlot>(
+    on: On<Remove, S>,
+    linked: Query<&S::Linked>,
+    mut commands: Commands,
+) {
+    if let Ok(linked) = linked.get(on.entity) {
+        for rt_id in linked.iter() {
+            // Already despawned along with the base, if it is being despawned
+            commands.entity(rt_id).try_despawn();
+        }
+    }
+}
+
+/// Moves the ruby text of a `Text` and of its spans along with the text when it is moved to
+/// another parent, as the ruby text is placed and transformed within the parent of the text.
+fn reparent_ruby_text(
+    on: On<Insert, ChildOf>,
+    parents: Query<&ChildOf>,
+    texts: Query<Option<&Children>, (With<Text>, Without<NoRuby>)>,
+    linked: Query<LinkedAnnotationTexts>,
+    outline_copies: Query<&RubyOutlineCopies>,
+    mut commands: Commands,
+) {
+    let parent = parents.get(on.entity).ok().map(ChildOf::parent);
+    move_ruby_text(
+        on.entity,
+        parent,
+        &parents,
+        &texts,
+        &linked,
+        &outline_copies,
+        &mut commands,
+    );
+}
+
+/// Makes the ruby text of a `Text` a root node along with the text.
+fn unparent_ruby_text(
+    on: On<Remove, ChildOf>,
+    parents: Query<&ChildOf>,
+    texts: Query<Option<&Children>, (With<Text>, Without<NoRuby>)>,
+    linked: Query<LinkedAnnotationTexts>,
+    outline_copies: Query<&RubyOutlineCopies>,
+    mut commands: Commands,
+) {
+    move_ruby_text(
+        on.entity,
+        None,
+        &parents,
+        &texts,
+        &linked,
+        &outline_copies,
+        &mut commands,
+    );
+}
+
+fn move_ruby_text(
+    text: Entity,
+    parent: Option<Entity>,
+    parents: &Query<&ChildOf>,
+    texts: &Query<Option<&Children>, (With<Text>, Without<NoRuby>)>,
+    linked: &Query<LinkedAnnotationTexts>,
+    outline_copies: &Query<&RubyOutlineCopies>,
+    commands: &mut Commands,
+) {
+    let Ok(spans) = texts.get(text) else {
+        return;
+    };
+    let ruby_texts = core::iter::once(text)
+        .chain(spans.into_iter().flatten().copied())
+        .filter_map(|base| linked.get(base).ok())
+        .flat_map(linked_annotation_texts);
+    for rt_id in ruby_texts {
+        let copies = outline_copies
+            .get(rt_id)
+            .into_iter()
+            .flat_map(|copies| copies.iter());
+        for entity in core::iter::once(rt_id).chain(copies) {
+            if parents.get(entity).ok().map(ChildOf::parent) == parent {
+                continue;
+            }
+            match parent {
+                Some(parent) => {
+                    commands.entity(entity).try_insert(ChildOf(parent));
+                }
+                // The ruby text may be despawned along with the text
+                None => {
+                    commands.entity(entity).try_remove::<ChildOf>();
+                }
+            }
+        }
     }
 }
 
-pub fn add_ruby_text_span(
-    on: On<Add, Ruby>,
-    ruby: Query<&Ruby, With<TextSpan>>,
-    text_config: Query<(&TextFont, &TextColor)>,
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn add_ruby_text_span<S: UiRubySlot>(
+    on: On<Add, (S, TextSpan, ChildOf)>,
+    ruby: Query<&S, (With<TextSpan>, Without<NoRuby>, Without<S::Linked>)>,
+    text_fonts: Query<&TextFont>,
+    text_colors: Query<&TextColor>,
     ancestors: Query<&ChildOf>,
     nodes: Query<&ZIndex, (With<Node>, With<Text>)>,
-    commands: Commands,
+    settings: Res<FuriganaSettings>,
+    ruby_texts: Query<(), AnyRubyText>,
+    children: Query<&Children>,
+    spans: Query<MergeableSpan, Without<NoRuby>>,
+    mut commands: Commands,
 ) {
-    if let Ok(ruby) = ruby.get(on.entity) {
+    if let Ok(slot) = ruby.get(on.entity) {
         let Ok(&ChildOf(parent)) = ancestors.get(on.entity) else {
             return;
         };
@@ -72,86 +290,395 @@ pub fn add_ruby_text_span(
             return;
         };
 
-        let Ok((text_font, color)) = text_config.get(on.entity) else {
+        // A span without its own style (e.g. removed from it) falls back to that of its parent
+        let (Ok(text_font), Ok(color)) = (
+            text_fonts
+                .get(on.entity)
+                .or_else(|_| text_fonts.get(parent)),
+            text_colors
+                .get(on.entity)
+                .or_else(|_| text_colors.get(parent)),
+        ) else {
             return;
         };
 
         let grandparent = ancestors.get(parent).ok().map(ChildOf::parent);
 
-        create_ruby_text(
-            on,
-            commands,
-            grandparent,
-            ruby,
-            text_font,
-            ruby.font_size_scale,
-            z_index,
-            *color,
-        );
+        if S::MERGEABLE
+            && settings.merge_adjacent_ruby
+            && let Ok(siblings) = children.get(parent)
+            && let Some(leader) = merge_leader(on.entity, siblings, &spans)
+        {
+            commands.entity(on.entity).insert(RubyMergedInto(leader));
+            return;
+        }
+
+        let ruby_text_count = ruby_texts.iter().count();
+        for (index, ruby) in slot.rubies().iter().enumerate() {
+            if ruby_budget_exceeded(&settings, ruby_text_count + index) {
+                return;
+            }
+
+            create_ruby_text::<S>(
+                on.entity,
+                index,
+                &mut commands,
+                grandparent,
+                ruby,
+                text_font,
+                &settings,
+                z_index,
+                *color,
+            );
+        }
     }
 }
 
-fn create_ruby_text(
-    on: On<Add, Ruby>,
-    mut commands: Commands,
+#[expect(clippy::too_many_arguments)]
+fn create_ruby_text<S: UiRubySlot>(
+    base: Entity,
+    index: usize,
+    commands: &mut Commands,
     parent: Option<Entity>,
     ruby: &Ruby,
     text_font: &TextFont,
-    font_size_scale: f32,
+    settings: &FuriganaSettings,
     z_index: ZIndex,
     text_color: TextColor,
 ) {
     let rt_id = commands
         .spawn((
-            RubyText(on.entity),
-            Text(ruby.rt.clone()),
+            S::link(base, index),
+            Text(ruby.display_text(settings.blank_ruby).into_owned()),
+            RubyFadeIn::default(),
+            // Hidden until placed
+            Visibility::Hidden,
             Node {
                 position_type: PositionType::Absolute,
                 ..default()
             },
-            // Order higher than original text
-            ZIndex(z_index.0 + 1),
-            ruby_text_font(text_font, font_size_scale),
+            // An absolutely positioned node may be squeezed at the edge of its parent, but the
+            // reading should stay on one line
+            TextLayout::new_with_no_wrap(),
+            // Order higher (or lower) than original text
+            ZIndex(z_index.0 + if settings.ruby_behind_base { -1 } else { 1 }),
+            ruby_text_font(ruby, text_font, settings),
             ruby.color.unwrap_or(text_color),
         ))
         .id();
+    set_ruby_shadow(&mut commands.entity(rt_id), ruby);
     if let Some(parent) = parent {
         commands.entity(parent).add_child(rt_id);
     }
+    commands.write_message(RubyTextSpawned {
+        base,
+        ruby_text: rt_id,
+    });
+}
+
+fn set_ruby_shadow(ruby_text: &mut EntityCommands, ruby: &Ruby) {
+    match ruby.shadow {
+        Some(shadow) => ruby_text.insert(TextShadow {
+            offset: shadow.offset,
+            color: shadow.color,
+        }),
+        None => ruby_text.remove::<TextShadow>(),
+    };
+}
+
+/// Copy of UI ruby text drawn behind it to make up its [`RubyOutline`](crate::RubyOutline).
+///
+/// A sibling rather than a child of the ruby text, which would then no longer be measured as text.
+#[derive(Component)]
+#[relationship(relationship_target = RubyOutlineCopies)]
+struct RubyOutlineOf {
+    #[relationship]
+    ruby_text: Entity,
+    direction: Vec2,
+}
+
+#[derive(Component)]
+#[relationship_target(relationship = RubyOutlineOf, linked_spawn)]
+struct RubyOutlineCopies(Vec<Entity>);
+
+/// Spawns, syncs and despawns the copies of ruby text making up its outline.
+#[expect(clippy::type_complexity)]
+fn update_ruby_outline(
+    ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText, &RubyTranslationText, &RubyStackText)>,
+            (Ref<Text>, Ref<TextFont>, Ref<TextColor>),
+            (Ref<Node>, Ref<Visibility>, Ref<ZIndex>, Ref<UiTransform>),
+            &UiGlobalTransform,
+            &ComputedNode,
+            Option<&ChildOf>,
+            Option<&RubyOutlineCopies>,
+        ),
+        Without<RubyOutlineOf>,
+    >,
+    rubies: Query<(
+        Option<Ref<Ruby>>,
+        Option<Ref<RubyTranslation>>,
+        Option<Ref<RubyStack>>,
+    )>,
+    mut copies: Query<(&RubyOutlineOf, &mut UiGlobalTransform)>,
+    mut commands: Commands,
+) {
+    for (
+        rt_entity,
+        link,
+        (text, font, color),
+        node_data,
+        global,
+        computed,
+        parent,
+        outline_copies,
+    ) in &ruby_text
+    {
+        let Some((src_id, annotation)) = annotation_of(link) else {
+            continue;
+        };
+        let Ok((reading, translation, stack)) = rubies.get(src_id) else {
+            continue;
+        };
+        let Some((ruby_changed, ruby)) = annotation_ruby(annotation, reading, translation, stack)
+        else {
+            continue;
+        };
+        let outline = ruby.outline;
+
+        let Some(outline) = outline else {
+            if outline_copies.is_some() {
+                commands
+                    .entity(rt_entity)
+                    .despawn_related::<RubyOutlineCopies>();
+            }
+            continue;
+        };
+
+        let (node, visibility, z_index, transform) = node_data;
+        let copy = |direction| {
+            let offset = outline.offset(direction, font.font_size);
+            (
+                Text(text.0.clone()),
+                font.clone(),
+                outline.text_color(*color),
+                TextLayout::new_with_no_wrap(),
+                Node {
+                    left: offset_val(node.left, offset.x),
+                    top: offset_val(node.top, offset.y),
+                    ..node.clone()
+                },
+                *visibility,
+                // Right behind the ruby text, which is a later sibling
+                ZIndex(z_index.0 - 1),
+                *transform,
+            )
+        };
+
+        let Some(outline_copies) = outline_copies else {
+            for direction in RubyOutline::DIRECTIONS {
+                let mut outline_copy = commands.spawn((
+                    RubyOutlineOf {
+                        ruby_text: rt_entity,
+                        direction,
+                    },
+                    copy(direction),
+                ));
+                if let Some(&ChildOf(parent)) = parent {
+                    outline_copy.insert(ChildOf(parent));
+                }
+            }
+            continue;
+        };
+
+        let changed = ruby_changed
+            || text.is_changed()
+            || font.is_changed()
+            || color.is_changed()
+            || node.is_changed()
+            || visibility.is_changed()
+            || z_index.is_changed()
+            || transform.is_changed();
+        for copy_id in outline_copies.iter() {
+            let Ok((&RubyOutlineOf { direction, .. }, mut copy_global)) = copies.get_mut(copy_id)
+            else {
+                continue;
+            };
+            if changed {
+                commands.entity(copy_id).insert(copy(direction));
+            }
+
+            // Follow the ruby text placed after layout within the same frame
+            let offset = outline.offset(direction, font.font_size) / computed.inverse_scale_factor;
+            copy_global.set_if_neq(UiGlobalTransform::from(
+                Affine2::from(*global) * Affine2::from_translation(offset),
+            ));
+        }
+    }
 }
 
-fn ruby_text_font(text_font: &TextFont, font_size_scale: f32) -> TextFont {
-    TextFont {
-        font_size: text_font.font_size * font_size_scale,
-        ..text_font.clone()
+fn offset_val(val: Val, offset: f32) -> Val {
+    match val {
+        Val::Px(px) => Val::Px(px + offset),
+        val => val,
     }
 }
 
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_ruby_text(
-    mut ruby_text: Query<(&RubyText, &mut Text, &mut TextFont, &mut TextColor), Without<Ruby>>,
-    ruby: Query<(Ref<Ruby>, Ref<TextFont>, &TextColor)>,
+    mut ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText, &RubyTranslationText, &RubyStackText)>,
+            &mut Text,
+            &mut TextFont,
+            &mut TextColor,
+            Option<&mut RubyFadeIn>,
+        ),
+        Without<Ruby>,
+    >,
+    ruby: Query<
+        (
+            Option<Ref<Ruby>>,
+            Option<Ref<RubyTranslation>>,
+            Option<Ref<RubyStack>>,
+            Ref<TextFont>,
+            &TextColor,
+            Option<&TextBackgroundColor>,
+            Option<Ref<RubyReveal>>,
+        ),
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    base_texts: Query<
+        AnyOf<(&Text, &TextSpan)>,
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    merged: Query<&MergedRuby>,
+    rubies: Query<&Ruby>,
+    mut removed_reveals: RemovedComponents<RubyReveal>,
+    settings: Res<FuriganaSettings>,
+    palette: Res<RubyPalette>,
+    time: Res<Time>,
+    mut commands: Commands,
 ) {
-    for (&RubyText(rt_id), mut text, mut ruby_font, mut ruby_text_color) in &mut ruby_text {
-        if let Ok((ruby, text_font, text_color)) = ruby.get(rt_id) {
-            if ruby.is_changed() && text.0 != ruby.rt {
-                text.0 = ruby.rt.clone();
+    let removed_reveals = removed_reveals.read().collect::<EntityHashSet>();
+    for (rt_entity, link, mut text, mut ruby_font, mut rt_color, fade_in) in &mut ruby_text {
+        let Some((rt_id, annotation)) = annotation_of(link) else {
+            continue;
+        };
+
+        if let Ok((reading, translation, stack, text_font, &text_color, background, reveal)) =
+            ruby.get(rt_id)
+        {
+            let Some((ruby_changed, ruby)) =
+                annotation_ruby(annotation, reading, translation, stack)
+            else {
+                continue;
+            };
+
+            let merged = (annotation == Annotation::Reading)
+                .then(|| merged.get(rt_id).ok())
+                .flatten();
+            let rt = merged_display_text(ruby, merged, &rubies, settings.blank_ruby);
+            let base_text = match base_texts.get(rt_id) {
+                Ok((Some(Text(text)), _) | (_, Some(TextSpan(text)))) => text.as_str(),
+                _ => "",
+            };
+            let revealed = reveal.as_ref().is_some_and(|reveal| reveal.is_changed())
+                || removed_reveals.contains(&rt_id);
+            let rt = revealed_text(rt, reveal.as_deref(), base_text);
+            // Spans merged into this one may change at any time
+            if (ruby_changed || settings.is_changed() || merged.is_some() || revealed)
+                && text.0 != rt
+            {
+                text.0 = rt.into_owned();
+            }
+
+            if text_font.is_changed() || ruby_changed || settings.is_changed() {
+                *ruby_font = ruby_text_font(ruby, &text_font, &settings);
+            }
+
+            if ruby_changed {
+                set_ruby_shadow(&mut commands.entity(rt_entity), ruby);
             }
 
-            if text_font.is_changed() {
-                *ruby_font = ruby_text_font(&text_font, ruby.font_size_scale);
+            *rt_color = ruby_text_color(ruby, text_color, background, &palette, &settings);
+
+            if let Some(mut fade_in) = fade_in {
+                if let Some(alpha) = fade_in.alpha(time.elapsed(), settings.ruby_fade_in) {
+                    let color = rt_color.0;
+                    rt_color.0 = color.with_alpha(color.alpha() * alpha);
+                } else {
+                    commands.entity(rt_entity).remove::<RubyFadeIn>();
+                }
             }
+        }
+    }
+}
 
-            *ruby_text_color = ruby.color.unwrap_or(*text_color);
+/// Sends [`RubyPositioned`] once the ruby text is placed, and [`RubyStabilized`] once it stays in
+/// place for a frame.
+#[expect(clippy::type_complexity)]
+fn track_ruby_settling(
+    mut ruby_text: Query<
+        (
+            Entity,
+            AnyOf<(&RubyText, &RubyTranslationText, &RubyStackText)>,
+            &mut RubySettling,
+            &Node,
+            &ComputedNode,
+            &Visibility,
+        ),
+        With<Text>,
+    >,
+    mut positioned: MessageWriter<RubyPositioned>,
+    mut stabilized: MessageWriter<RubyStabilized>,
+) {
+    for (rt_id, link, mut settling, node, computed_node, visibility) in &mut ruby_text {
+        let Some((base, _)) = annotation_of(link) else {
+            continue;
+        };
+        let placement = match (node.left, node.top) {
+            (Val::Px(left), Val::Px(top)) if *visibility != Visibility::Hidden => {
+                Some((Vec3::new(left, top, 0.0), computed_node.size()))
+            }
+            _ => None,
+        };
+        if settling.position(placement.is_some()) {
+            positioned.write(RubyPositioned {
+                base,
+                ruby_text: rt_id,
+            });
+        }
+        if settling.settle(placement) {
+            stabilized.write(RubyStabilized { base, ruby: rt_id });
         }
     }
 }
 
+#[expect(clippy::type_complexity)]
 pub fn update_ruby_display(
-    ruby_text: Query<&RubyText>,
+    ruby_text: Query<(
+        Entity,
+        AnyOf<(&RubyText, &RubyTranslationText, &RubyStackText)>,
+    )>,
     text_root: Query<TextRootEntity>,
     mut nodes: Query<Mut<Node>>,
 ) {
-    for &RubyText(src_id) in &ruby_text {
+    for (rt_id, link) in &ruby_text {
+        let Some((src_id, _)) = annotation_of(link) else {
+            continue;
+        };
         let Some(text_root) = text_root.get(src_id).ok().and_then(|tr| tr.get()) else {
             continue;
         };
@@ -165,7 +692,7 @@ pub fn update_ruby_display(
 
         let display = text_root_node.display;
 
-        if let Ok(mut node) = nodes.get_mut(src_id)
+        if let Ok(mut node) = nodes.get_mut(rt_id)
             && node.display != display
         {
             node.display = display;
@@ -173,116 +700,758 @@ pub fn update_ruby_display(
     }
 }
 
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_ruby(
-    text_layouts: Query<(&TextLayoutInfo, &Node), Without<RubyText>>,
-    mut node_query: Query<(&ComputedNode, &mut UiGlobalTransform, &mut UiTransform)>,
-    ruby_query: Query<(Entity, Ref<Ruby>, &LinkedRubyText, TextRootEntity), Without<RubyText>>,
+    text_layouts: Query<
+        (Ref<TextLayoutInfo>, &Node),
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    mut node_query: Query<(Ref<ComputedNode>, &mut UiGlobalTransform, &mut UiTransform)>,
+    ruby_query: Query<(
+        Entity,
+        Ref<Ruby>,
+        &LinkedRubyText,
+        TextRootEntity,
+        Option<Ref<MergedRuby>>,
+    )>,
+    outer_annotations: OuterAnnotations,
     ancestors: Query<&ChildOf>,
-    mut ruby_nodes: Query<&mut Node, (With<RubyText>, Without<Ruby>)>,
+    disabled: Query<(), With<FuriganaDisabled>>,
+    visibilities: Query<
+        &Visibility,
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    mut ruby_nodes: Query<
+        (&mut Node, &mut Visibility),
+        Or<(
+            With<RubyText>,
+            With<RubyTranslationText>,
+            With<RubyStackText>,
+        )>,
+    >,
+    mut overhangs: Query<&mut RubyOverhang>,
+    mut ruby_layouts: Query<
+        &mut TextLayoutInfo,
+        Or<(
+            With<RubyText>,
+            With<RubyTranslationText>,
+            With<RubyStackText>,
+        )>,
+    >,
+    mut text_reader: TextUiReader,
+    text_fonts: Query<&TextFont>,
+    fonts: Res<Assets<Font>>,
     settings: Res<FuriganaSettings>,
+    mut section_rects: Local<SectionRectsCache>,
+    mut placed_roots: Local<PlacedRoots>,
 ) {
-    for (text_entity, ruby, &LinkedRubyText(rt_id), text_root_node) in &ruby_query {
-        let Some(text_root_id) = text_root_node.get() else {
+    // Group rubies by their text root so that per-root data is fetched once
+    let mut rubies_by_root = EntityHashMap::<Vec<_>>::default();
+    let mut roots_with_changed_ruby = EntityHashSet::default();
+    // Readings first, so that annotations on the same side are stacked outward of them
+    let outer_annotations =
+        outer_annotations
+            .iter()
+            .map(|(text_entity, ruby_changed, ruby, rt_id, text_root_id)| {
+                (text_entity, ruby_changed, ruby, rt_id, text_root_id, None)
+            });
+    for (text_entity, ruby_changed, ruby, rt_id, text_root_id, merged) in ruby_query
+        .iter()
+        .map(|(text_entity, ruby, linked, text_root_node, merged)| {
+            (
+                text_entity,
+                ruby.is_changed() || merged.as_ref().is_some_and(DetectChanges::is_changed),
+                ruby.into_inner(),
+                linked.entity(),
+                text_root_node.get(),
+                merged.map(Ref::into_inner),
+            )
+        })
+        .chain(outer_annotations)
+    {
+        let Some(text_root_id) = text_root_id else {
             error!("No text root entity for {text_entity:?}");
             continue;
         };
+        if ruby_changed {
+            roots_with_changed_ruby.insert(text_root_id);
+        }
+        rubies_by_root
+            .entry(text_root_id)
+            .or_default()
+            .push((text_entity, ruby, rt_id, merged));
+    }
 
+    for (text_root_id, rubies) in rubies_by_root {
         let Ok((layout_info, node)) = text_layouts.get(text_root_id) else {
             continue;
         };
 
         if node.display == Display::None {
-            if let Ok(mut node) = ruby_nodes.get_mut(rt_id) {
-                node.display = Display::None;
+            for &(_, _, rt_id, _) in &rubies {
+                if let Ok((mut node, _)) = ruby_nodes.get_mut(rt_id)
+                    && node.display != Display::None
+                {
+                    node.display = Display::None;
+                }
             }
             continue;
         }
 
-        let Some(section_rect) = layout_info
-            .section_rects
-            .iter()
-            .find(|&&(id, _)| id == text_entity)
-            .map(|&(_, rect)| rect)
-        else {
+        if root_ruby_hidden(text_root_id, &ancestors, &disabled, |entity| {
+            visibilities.get(entity).ok().copied()
+        }) {
+            for &(_, _, rt_id, _) in &rubies {
+                if let Ok((_, mut visibility)) = ruby_nodes.get_mut(rt_id) {
+                    visibility.set_if_neq(Visibility::Hidden);
+                }
+            }
             continue;
-        };
-
-        let (parent_global, parent_computed) = if let Ok(&ChildOf(node_parent)) =
-            ancestors.get(text_root_id)
-            && let Ok((parent_computed, parent_global, _)) = node_query.get(node_parent)
-        {
-            (*parent_global, *parent_computed)
-        } else {
-            (UiGlobalTransform::default(), ComputedNode::default())
-        };
+        }
 
-        let Ok((&node_computed, &node_global_transform, &node_transform)) =
+        let Ok((node_computed, &node_global_transform, &node_transform)) =
             node_query.get(text_root_id)
         else {
             continue;
         };
+        let node_computed = *node_computed;
 
-        let Ok((ruby_computed_node, mut rt_global_transform, mut rt_transform)) =
-            node_query.get_mut(rt_id)
-        else {
+        // Static text is left as placed, while ruby follows changes of the layout and transform of
+        // the base and its parent, and of the size of the ruby text itself
+        let ruby_count = rubies.len();
+        let changed = settings.is_changed()
+            || layout_info.is_changed()
+            || roots_with_changed_ruby.contains(&text_root_id)
+            || core::iter::once(text_root_id)
+                .chain(ancestors.get(text_root_id).ok().map(ChildOf::parent))
+                .any(|entity| {
+                    node_query.get_mut(entity).is_ok_and(
+                        |(computed, global_transform, transform)| {
+                            computed.is_changed()
+                                || global_transform.is_changed()
+                                || transform.is_changed()
+                        },
+                    )
+                })
+            || rubies.iter().any(|&(_, _, rt_id, _)| {
+                node_query
+                    .get(rt_id)
+                    .is_ok_and(|(ruby_computed_node, _, _)| ruby_computed_node.is_changed())
+            });
+        if placed_roots.unchanged(text_root_id, ruby_count, changed) {
             continue;
-        };
+        }
 
-        let ruby_pos_local_topleft = Vec2::new(
-            match ruby.align {
-                RubyAlign::Start => section_rect.min.x + ruby_computed_node.size().x / 2.0,
-                RubyAlign::Center => f32::midpoint(section_rect.min.x, section_rect.max.x),
-                RubyAlign::End => section_rect.max.x - ruby_computed_node.size().x / 2.0,
-            },
-            match ruby.position {
-                RubyPosition::Over => section_rect.min.y,
-                RubyPosition::Under => section_rect.max.y,
-            },
+        let lines = text_lines(
+            text_reader
+                .iter(text_root_id)
+                .map(|(_, _, text, _, _)| text),
+        );
+        let non_cjk = non_cjk_sections(
+            text_reader
+                .iter(text_root_id)
+                .map(|(entity, _, text, _, _)| (entity, text)),
+            &settings,
+        );
+        let block = TextBlock::with_section_rects(
+            &layout_info,
+            section_rects.get(text_root_id, &layout_info, layout_info.is_changed()),
+            &lines,
         );
 
-        let ruby_pos_local = ruby_pos_local_topleft - node_computed.size() / 2.0;
+        let block_rubies = rubies
+            .iter()
+            .map(|&(text_entity, ruby, rt_id, merged)| {
+                let ruby_size = node_query
+                    .get(rt_id)
+                    .ok()
+                    .map(|(ruby_computed_node, _, _)| ruby_computed_node.size());
+                (text_entity, ruby, merged, ruby_size)
+            })
+            .collect::<Vec<_>>();
+        let ruby_boxes = place_block_rubies(
+            &block,
+            &block_rubies,
+            &non_cjk,
+            &text_fonts,
+            &fonts,
+            &settings,
+        );
 
-        let ruby_pos_global = node_global_transform.transform_point2(ruby_pos_local);
+        for (&(text_entity, ruby, rt_id, merged), ruby_box) in rubies.iter().zip(ruby_boxes) {
+            let Some(ruby_box) = ruby_box else {
+                // No CJK text with `cjk_only`, or not laid out yet (e.g. the font is still loading)
+                if let Ok((_, mut visibility)) = ruby_nodes.get_mut(rt_id) {
+                    visibility.set_if_neq(Visibility::Hidden);
+                }
+                continue;
+            };
 
-        rt_transform.scale = node_transform.scale;
-        rt_transform.rotation = node_transform.rotation;
+            if ruby.align.spreads_glyphs()
+                && let Ok(mut ruby_layout_info) = ruby_layouts.get_mut(rt_id)
+                && justify_glyphs(
+                    ruby_layout_info.bypass_change_detection(),
+                    ruby_box.size.x / ruby_box.scale,
+                    ruby.align,
+                )
+            {
+                ruby_layout_info.set_changed();
+            }
 
-        if settings.update_ui_global_transform {
-            let (text_scale, text_angle, _) = node_global_transform.to_scale_angle_translation();
+            if let Some(base_centers) = mono_ruby_base_centers(
+                &block,
+                text_entity,
+                ruby,
+                merged,
+                settings.section_rect_tie_break,
+            ) && let Ok(mut ruby_layout_info) = ruby_layouts.get_mut(rt_id)
+            {
+                let origin_x = ruby_layout_info.size.x / 2.0;
+                if center_mono_ruby(
+                    ruby,
+                    &base_centers,
+                    &ruby_box,
+                    ruby_layout_info.bypass_change_detection(),
+                    origin_x,
+                ) {
+                    ruby_layout_info.set_changed();
+                }
+            }
 
-            rt_global_transform.set_if_neq(UiGlobalTransform::from(
-                Affine2::from_scale_angle_translation(text_scale, text_angle, ruby_pos_global),
-            ));
-        }
+            // The ruby text is positioned within its own parent, which is that of the base text
+            // once `reparent_ruby_text` has caught up with the base being moved
+            let (parent_global, parent_computed) = if let Ok(&ChildOf(rt_parent)) =
+                ancestors.get(rt_id)
+                && let Ok((parent_computed, parent_global, _)) = node_query.get(rt_parent)
+            {
+                (*parent_global, *parent_computed)
+            } else {
+                (UiGlobalTransform::default(), ComputedNode::default())
+            };
 
-        let Ok(mut node) = ruby_nodes.get_mut(rt_id) else {
-            error!("No ruby text node for entity {:?}", rt_id);
-            continue;
-        };
+            let Ok((ruby_computed_node, mut rt_global_transform, mut rt_transform)) =
+                node_query.get_mut(rt_id)
+            else {
+                continue;
+            };
 
-        if node.display != Display::default() {
-            node.display = Display::default();
-        }
+            let ruby_pos_local = ruby_box.center - node_computed.size() / 2.0;
 
-        let ruby_top_left = parent_global.inverse().transform_point2(ruby_pos_global)
-            + parent_computed.size() / 2.0
-            - Vec2::new(parent_computed.border().left, parent_computed.border().top)
-            - ruby_computed_node.size() / 2.0;
-        let new_top = Val::Px(ruby_top_left.y * parent_computed.inverse_scale_factor);
-        let new_left = Val::Px(ruby_top_left.x * parent_computed.inverse_scale_factor);
-        if node.top != new_top {
-            node.top = new_top;
-        }
-        if node.left != new_left {
-            node.left = new_left;
+            let ruby_pos_global = node_global_transform.transform_point2(ruby_pos_local);
+
+            // Translation of the base (including its `UiTransform::translation`) is already
+            // captured by its global transform, so only scale and rotation are copied.
+            let rt_scale = node_transform.scale * ruby_box.scale;
+            if rt_transform.scale != rt_scale || rt_transform.rotation != node_transform.rotation {
+                rt_transform.scale = rt_scale;
+                rt_transform.rotation = node_transform.rotation;
+            }
+
+            if settings.update_ui_global_transform {
+                let (text_scale, text_angle, _) =
+                    node_global_transform.to_scale_angle_translation();
+
+                rt_global_transform.set_if_neq(UiGlobalTransform::from(
+                    Affine2::from_scale_angle_translation(
+                        text_scale * ruby_box.scale,
+                        text_angle,
+                        ruby_pos_global,
+                    ),
+                ));
+            }
+
+            if let Ok(mut overhang) = overhangs.get_mut(rt_id) {
+                overhang.set_if_neq(ruby_box.to_logical(layout_info.scale_factor).overhang());
+            }
+
+            let Ok((mut node, mut visibility)) = ruby_nodes.get_mut(rt_id) else {
+                error!("No ruby text node for entity {:?}", rt_id);
+                continue;
+            };
+
+            if node.display != Display::default() {
+                node.display = Display::default();
+            }
+
+            let ruby_top_left = parent_global.inverse().transform_point2(ruby_pos_global)
+                + parent_computed.size() / 2.0
+                - Vec2::new(parent_computed.border().left, parent_computed.border().top)
+                - ruby_computed_node.size() / 2.0;
+            // Of the base rather than the parent, which is a default one for a root node
+            let new_top = Val::Px(ruby_top_left.y * node_computed.inverse_scale_factor);
+            let new_left = Val::Px(ruby_top_left.x * node_computed.inverse_scale_factor);
+            if node.top != new_top {
+                node.top = new_top;
+            }
+            if node.left != new_left {
+                node.left = new_left;
+            }
+
+            // Reveal once the ruby text itself has been laid out
+            if ruby_computed_node.size() != Vec2::ZERO {
+                visibility.set_if_neq(Visibility::Inherited);
+            }
         }
+        placed_roots.placed(text_root_id, ruby_count);
+    }
+    section_rects.retain_used();
+    placed_roots.finish_run();
+}
+
+/// Lays out UI text (including ruby text synced with its [`Ruby`]) and nodes right away, running
+/// the systems Bevy runs for them in `PostUpdate`.
+///
+/// Systems whose resources are missing (e.g. without `UiPlugin`) are skipped.
+pub(crate) fn lay_out_ui(world: &mut World) {
+    let _ = world.run_system_cached(update_ruby_text);
+    let _ = world.run_system_cached(propagate_ui_target_cameras);
+    propagate_to_ui_children::<ComputedUiTargetCamera>(world);
+    propagate_to_ui_children::<ComputedUiRenderTargetInfo>(world);
+    let _ = world.run_system_cached(detect_text_needs_rerender::<Text>);
+    let _ = world.run_system_cached(measure_text_system);
+    let _ = world.run_system_cached(ui_layout_system);
+    let _ = world.run_system_cached(text_system);
+}
+
+/// Propagates `C` from UI roots to their descendants, as `HierarchyPropagatePlugin` does.
+fn propagate_to_ui_children<C: Component + Clone + PartialEq>(world: &mut World) {
+    let _ = world.run_system_cached(update_source::<C, ()>);
+    let _ = world.run_system_cached(update_stopped::<C, ()>);
+    let _ = world.run_system_cached(update_reparented::<C, (), ChildOf>);
+    let _ = world.run_system_cached(propagate_inherited::<C, (), ChildOf>);
+    let _ = world.run_system_cached(propagate_output::<C, ()>);
+}
+
+#[expect(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn ruby_placement(
+    In(base): In<Entity>,
+    ruby_query: Query<(
+        Entity,
+        &Ruby,
+        &LinkedRubyText,
+        TextRootEntity,
+        Option<&MergedRuby>,
+    )>,
+    outer_annotations: OuterAnnotations,
+    text_layouts: Query<
+        (&TextLayoutInfo, &Node, &ComputedNode, &UiGlobalTransform),
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    ruby_nodes: Query<
+        &ComputedNode,
+        Or<(
+            With<RubyText>,
+            With<RubyTranslationText>,
+            With<RubyStackText>,
+        )>,
+    >,
+    ancestors: Query<&ChildOf>,
+    disabled: Query<(), With<FuriganaDisabled>>,
+    visibilities: Query<
+        &Visibility,
+        (
+            Without<RubyText>,
+            Without<RubyTranslationText>,
+            Without<RubyStackText>,
+        ),
+    >,
+    mut text_reader: TextUiReader,
+    text_fonts: Query<&TextFont>,
+    fonts: Res<Assets<Font>>,
+    settings: Res<FuriganaSettings>,
+) -> Option<RubyPlacement> {
+    let text_root_id = ruby_query.get(base).ok()?.3.get()?;
+    let (layout_info, node, node_computed, node_global_transform) =
+        text_layouts.get(text_root_id).ok()?;
+    if node.display == Display::None
+        || root_ruby_hidden(text_root_id, &ancestors, &disabled, |entity| {
+            visibilities.get(entity).ok().copied()
+        })
+    {
+        return None;
+    }
+
+    // All rubies of the text root, in the same order as in `update_ruby`, since they are placed
+    // against each other
+    let outer_annotations =
+        outer_annotations
+            .iter()
+            .map(|(text_entity, _, ruby, rt_id, text_root)| {
+                (text_entity, ruby, rt_id, text_root, None)
+            });
+    let rubies = ruby_query
+        .iter()
+        .map(|(text_entity, ruby, linked, text_root, merged)| {
+            (text_entity, ruby, linked.entity(), text_root.get(), merged)
+        })
+        .chain(outer_annotations)
+        .filter(|&(_, _, _, text_root, _)| text_root == Some(text_root_id))
+        .map(|(text_entity, ruby, rt_id, _, merged)| {
+            let ruby_size = ruby_nodes.get(rt_id).ok().map(ComputedNode::size);
+            (text_entity, ruby, merged, ruby_size)
+        })
+        .collect::<Vec<_>>();
+    // Rubies come before other annotations, so this is the ruby rather than another annotation of
+    // `base`
+    let index = rubies
+        .iter()
+        .position(|&(text_entity, _, _, _)| text_entity == base)?;
+
+    let lines = text_lines(
+        text_reader
+            .iter(text_root_id)
+            .map(|(_, _, text, _, _)| text),
+    );
+    let non_cjk = non_cjk_sections(
+        text_reader
+            .iter(text_root_id)
+            .map(|(entity, _, text, _, _)| (entity, text)),
+        &settings,
+    );
+    let block = TextBlock::new(layout_info, &lines);
+    let ruby_box = place_block_rubies(&block, &rubies, &non_cjk, &text_fonts, &fonts, &settings)
+        .swap_remove(index)?;
+
+    let ruby_center =
+        node_global_transform.transform_point2(ruby_box.center - node_computed.size() / 2.0);
+
+    Some(RubyPlacement::new(
+        ruby_box.base_rect,
+        ruby_box,
+        layout_info.scale_factor,
+        ruby_center.extend(0.0),
+    ))
+}
+
+/// Window rect of the annotation text of `base` in slot `S`.
+#[expect(clippy::type_complexity)]
+pub(crate) fn ruby_window_rect<S: UiRubySlot>(
+    In(base): In<Entity>,
+    linked: Query<&S::Linked>,
+    ruby_nodes: Query<
+        (
+            &ComputedNode,
+            &UiGlobalTransform,
+            &Visibility,
+            Option<&ComputedUiTargetCamera>,
+        ),
+        Or<(
+            With<RubyText>,
+            With<RubyTranslationText>,
+            With<RubyStackText>,
+        )>,
+    >,
+    cameras: Query<&Camera>,
+) -> Option<Rect> {
+    // The first annotation text of a slot with several of them
+    let rt_id = linked.get(base).ok()?.iter().next()?;
+    let (node, transform, visibility, target_camera) = ruby_nodes.get(rt_id).ok()?;
+    if *visibility == Visibility::Hidden {
+        return None;
     }
+
+    // UI is laid out relative to the viewport of its camera
+    let viewport_min = target_camera
+        .and_then(ComputedUiTargetCamera::get)
+        .and_then(|camera| cameras.get(camera).ok())
+        .and_then(Camera::physical_viewport_rect)
+        .map_or(Vec2::ZERO, |viewport| viewport.min.as_vec2());
+
+    let half_size = node.size() / 2.0;
+    let rect = bounding_rect(
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+            .map(|(x, y)| transform.transform_point2(half_size * Vec2::new(x, y))),
+    );
+    Some(Rect::from_corners(
+        (rect.min + viewport_min) * node.inverse_scale_factor,
+        (rect.max + viewport_min) * node.inverse_scale_factor,
+    ))
 }
 
 #[cfg(test)]
 mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
     use super::*;
 
+    /// Spawns an annotated text node of `text_node` size centered at `text_center`,
+    /// inside a 200x100 parent node at the origin, and returns the text and ruby text entities.
+    fn spawn_laid_out_text(
+        world: &mut World,
+        text_node: ComputedNode,
+        text_center: Vec2,
+        section_rect: Rect,
+    ) -> (Entity, Entity) {
+        world.init_resource::<Assets<Font>>();
+
+        let parent = world
+            .spawn((
+                Node::default(),
+                ComputedNode {
+                    size: Vec2::new(200.0, 100.0),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(100.0, 50.0))),
+            ))
+            .id();
+
+        let text_entity = world
+            .spawn((Text::new("漢字"), Ruby::new("かんじ"), ChildOf(parent)))
+            .id();
+        world.entity_mut(text_entity).insert((
+            text_node,
+            UiGlobalTransform::from(Affine2::from_translation(text_center)),
+            TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![(text_entity, section_rect)],
+                size: section_rect.size(),
+            },
+        ));
+
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        world.entity_mut(rt_id).insert(ComputedNode {
+            size: Vec2::new(30.0, 10.0),
+            ..default()
+        });
+
+        (text_entity, rt_id)
+    }
+
+    /// App with the resources of the real UI layout and the default font, and a 400x300 camera.
+    /// Layout is run with [`lay_out_ui`] rather than by updating the app.
+    fn ui_layout_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            TaskPoolPlugin::default(),
+            AssetPlugin::default(),
+            bevy::text::TextPlugin,
+            bevy::ui::UiPlugin,
+            crate::FuriganaPlugin,
+        ))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_resource::<Time>();
+
+        let world = app.world_mut();
+        world
+            .resource_mut::<Assets<Font>>()
+            .insert(
+                AssetId::default(),
+                Font::try_from_bytes(bevy::text::DEFAULT_FONT_DATA.to_vec()).unwrap(),
+            )
+            .unwrap();
+        world.spawn((
+            Camera {
+                computed: bevy::camera::ComputedCameraValues {
+                    target_info: Some(bevy::camera::RenderTargetInfo {
+                        physical_size: UVec2::new(400, 300),
+                        scale_factor: 1.0,
+                    }),
+                    ..default()
+                },
+                ..default()
+            },
+            IsDefaultUiCamera,
+        ));
+        app
+    }
+
+    /// Offsets of the rubies of a text in a row from the top center of their bases, after
+    /// placing them on the real layout.
+    fn laid_out_ruby_offsets(row: Node, text_node: Node, moved_into_row: bool) -> Vec<Vec2> {
+        let mut app = ui_layout_app();
+        let world = app.world_mut();
+        let text_font = TextFont::from_font_size(20.0);
+        let row = world.spawn(row).id();
+        let text = (
+            Text::new("AA\n"),
+            text_font.clone(),
+            Ruby::new("aa"),
+            text_node,
+        );
+        let text = if moved_into_row {
+            world.spawn(text).id()
+        } else {
+            world.spawn((text, ChildOf(row))).id()
+        };
+        let span = world
+            .spawn((
+                TextSpan::new("BBBB"),
+                text_font,
+                Ruby::new("bbbbbb"),
+                ChildOf(text),
+            ))
+            .id();
+        if moved_into_row {
+            // After the ruby texts have been spawned without the row as their parent
+            world.entity_mut(row).add_child(text);
+        }
+
+        // Once to lay out the ruby text, and again to lay it out where it is placed
+        for _ in 0..2 {
+            lay_out_ui(world);
+            world.run_system_cached(update_ruby).unwrap();
+        }
+        lay_out_ui(world);
+
+        let text_size = world.get::<ComputedNode>(text).unwrap().size();
+        let text_global = *world.get::<UiGlobalTransform>(text).unwrap();
+        let section_rects = world
+            .get::<TextLayoutInfo>(text)
+            .unwrap()
+            .section_rects
+            .clone();
+        [text, span]
+            .into_iter()
+            .map(|base| {
+                let rt_id = world.get::<LinkedRubyText>(base).unwrap().entity();
+                assert_eq!(world.get::<ChildOf>(rt_id), Some(&ChildOf(row)));
+                let rt_center = world.get::<UiGlobalTransform>(rt_id).unwrap().translation;
+                let (_, rect) = section_rects.iter().find(|(id, _)| *id == base).unwrap();
+                text_global.inverse().transform_point2(rt_center) + text_size / 2.0
+                    - Vec2::new(rect.center().x, rect.min.y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ruby_placement_in_laid_out_row() {
+        let row = Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        };
+        let expected = laid_out_ruby_offsets(row.clone(), Node::default(), false);
+
+        let bordered_row = Node {
+            border: UiRect::all(px(10.0)),
+            margin: UiRect::top(px(100.0)),
+            padding: UiRect::all(px(10.0)),
+            ..row.clone()
+        };
+        let text_nodes = [
+            Node::default(),
+            Node {
+                margin: UiRect::left(px(20.0)),
+                ..default()
+            },
+            Node {
+                left: px(13.0),
+                top: px(9.0),
+                ..default()
+            },
+        ];
+        for (i, text_node) in text_nodes.into_iter().enumerate() {
+            for moved_into_row in [false, true] {
+                let offsets =
+                    laid_out_ruby_offsets(bordered_row.clone(), text_node.clone(), moved_into_row);
+                for (offset, expected) in offsets.into_iter().zip(&expected) {
+                    assert!(
+                        offset.abs_diff_eq(*expected, 0.01),
+                        "{offset} != {expected} with text node {i}, moved: {moved_into_row}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ruby_placement_on_laid_out_padded_text() {
+        let row = Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        };
+        let expected = laid_out_ruby_offsets(row.clone(), Node::default(), false);
+
+        // Text glyphs are drawn from the top-left of the node regardless of its padding and
+        // border, which only grow the node, so the ruby must be placed the same way
+        let text_nodes = [
+            Node {
+                padding: UiRect::all(px(7.0)),
+                ..default()
+            },
+            Node {
+                padding: UiRect::new(px(3.0), px(11.0), px(5.0), px(13.0)),
+                border: UiRect::all(px(2.0)),
+                ..default()
+            },
+        ];
+        for (i, text_node) in text_nodes.into_iter().enumerate() {
+            let offsets = laid_out_ruby_offsets(row.clone(), text_node, false);
+            for (offset, expected) in offsets.into_iter().zip(&expected) {
+                assert!(
+                    offset.abs_diff_eq(*expected, 0.01),
+                    "{offset} != {expected} with text node {i}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_mono_ruby_readings_over_their_characters() {
+        let mut app = ui_layout_app();
+        let world = app.world_mut();
+        let text = world
+            .spawn((
+                Text::new("AB"),
+                TextFont::from_font_size(20.0),
+                Ruby::mono(["xy", "z"]),
+            ))
+            .id();
+        for _ in 0..3 {
+            lay_out_ui(world);
+            world.run_system_cached(update_ruby).unwrap();
+        }
+
+        let text_global = *world.get::<UiGlobalTransform>(text).unwrap();
+        let text_size = world.get::<ComputedNode>(text).unwrap().size();
+        let base_rect = world.get::<TextLayoutInfo>(text).unwrap().section_rects[0].1;
+        let rt_id = world.get::<LinkedRubyText>(text).unwrap().entity();
+        let rt_global = *world.get::<UiGlobalTransform>(rt_id).unwrap();
+        let rt_size = world.get::<ComputedNode>(rt_id).unwrap().size();
+        let rt_layout_info = world.get::<TextLayoutInfo>(rt_id).unwrap();
+
+        // The default font is monospace, so each character of the base takes half of it
+        let char_width = base_rect.width() / 2.0;
+        for (index, bytes) in [0..2, 2..3].into_iter().enumerate() {
+            let (left, right) = rt_layout_info
+                .glyphs
+                .iter()
+                .filter(|glyph| bytes.contains(&glyph.byte_index))
+                .map(|glyph| {
+                    let half_width = glyph.size.x / 2.0;
+                    (glyph.position.x - half_width, glyph.position.x + half_width)
+                })
+                .reduce(|(a, b), (c, d)| (a.min(c), b.max(d)))
+                .unwrap();
+            let reading_center = rt_global
+                .transform_point2(Vec2::new(f32::midpoint(left, right), 0.0) - rt_size / 2.0)
+                .x;
+            let char_center = text_global
+                .transform_point2(
+                    Vec2::new(base_rect.min.x + char_width * (index as f32 + 0.5), 0.0)
+                        - text_size / 2.0,
+                )
+                .x;
+            assert!(
+                (reading_center - char_center).abs() < 0.5,
+                "reading {index} at {reading_center} != {char_center}"
+            );
+        }
+    }
+
     #[test]
     fn test_add_ruby_creates_ruby_text() {
         let mut app = App::new();
@@ -296,6 +1465,13 @@ mod tests {
         let linked = app.world().get::<LinkedRubyText>(text_entity).unwrap();
         let ruby_text = app.world().get::<Text>(linked.entity()).unwrap();
         assert_eq!(ruby_text.0, "ruby");
+        assert_eq!(
+            app.world()
+                .get::<TextLayout>(linked.entity())
+                .unwrap()
+                .linebreak,
+            LineBreak::NoWrap
+        );
 
         // 2D counterpart must not be created
         #[cfg(feature = "text2d")]
@@ -305,4 +1481,1427 @@ mod tests {
                 .is_none()
         );
     }
+
+    #[test]
+    fn test_ruby_reveal() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.init_resource::<Time>();
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("東京"),
+                Ruby::new("とうきょう"),
+                crate::RubyReveal::new(0),
+            ))
+            .id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        let ruby_text = |world: &mut World| {
+            world.run_system_once(update_ruby_text).unwrap();
+            world.get::<Text>(rt_id).unwrap().0.clone()
+        };
+
+        assert_eq!(ruby_text(world), "");
+        world
+            .get_mut::<crate::RubyReveal>(text_entity)
+            .unwrap()
+            .base_chars_shown = 1;
+        assert_eq!(ruby_text(world), "とう");
+
+        world.entity_mut(text_entity).remove::<crate::RubyReveal>();
+        assert_eq!(ruby_text(world), "とうきょう");
+    }
+
+    #[test]
+    fn test_ruby_outline() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let outline = RubyOutline {
+            color: Color::WHITE,
+            width: 0.1,
+        };
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("犬"),
+                Ruby {
+                    outline: Some(outline),
+                    ..Ruby::new("いぬ")
+                },
+            ))
+            .id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        let mut rt_node = world.get_mut::<Node>(rt_id).unwrap();
+        (rt_node.left, rt_node.top) = (Val::Px(10.0), Val::Px(5.0));
+        world.run_system_once(update_ruby_outline).unwrap();
+
+        let mut copies = world.query::<(&RubyOutlineOf, &Text, &TextColor, &Node)>();
+        assert_eq!(copies.iter(world).count(), 8);
+        for (_, text, color, _) in copies.iter(world) {
+            assert_eq!((text.0.as_str(), color.0), ("いぬ", Color::WHITE));
+        }
+        let (_, _, _, node) = copies
+            .iter(world)
+            .find(|(copy, ..)| copy.direction == Vec2::X)
+            .unwrap();
+        // 10% of the default ruby font size, half of the base's 20px
+        assert_eq!((node.left, node.top), (Val::Px(11.0), Val::Px(5.0)));
+
+        world.get_mut::<Ruby>(text_entity).unwrap().outline = None;
+        world.run_system_once(update_ruby_outline).unwrap();
+        assert_eq!(copies.iter(world).count(), 0);
+    }
+
+    #[test]
+    fn test_removing_text_despawns_ruby_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("犬"),
+                Ruby::new("いぬ"),
+                RubyTranslation::new("dog"),
+            ))
+            .id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        let translation_id = world
+            .get::<LinkedRubyTranslationText>(text_entity)
+            .unwrap()
+            .entity();
+
+        world.entity_mut(text_entity).remove::<Text>();
+        world.flush();
+
+        assert!(world.get_entity(rt_id).is_err());
+        assert!(world.get_entity(translation_id).is_err());
+        assert!(world.get::<Ruby>(text_entity).is_some());
+    }
+
+    #[test]
+    fn test_removing_ruby_despawns_ruby_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("犬"),
+                Ruby::new("いぬ"),
+                RubyTranslation::new("dog"),
+            ))
+            .id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        let translation_id = world
+            .get::<LinkedRubyTranslationText>(text_entity)
+            .unwrap()
+            .entity();
+
+        world.entity_mut(text_entity).remove::<Ruby>();
+        world.flush();
+        assert!(world.get_entity(rt_id).is_err());
+        assert!(world.get::<LinkedRubyText>(text_entity).is_none());
+        assert!(world.get_entity(translation_id).is_ok());
+
+        // Tearing down the base despawns the rest only once
+        world.despawn(text_entity);
+        world.flush();
+        assert!(world.get_entity(translation_id).is_err());
+    }
+
+    #[test]
+    fn test_base_text_edits_keep_ruby_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text::new("漢字"), Ruby::new("かんじ"))).id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+
+        world.get_mut::<Text>(text_entity).unwrap().0 = "漢字です".into();
+        world.run_system_once(update_ruby_text).unwrap();
+        // Replaced rather than removed
+        world.entity_mut(text_entity).insert(Text::new("感じ"));
+        world.run_system_once(update_ruby_text).unwrap();
+
+        assert_eq!(
+            world.get::<LinkedRubyText>(text_entity).unwrap().entity(),
+            rt_id
+        );
+        assert_eq!(world.get::<Text>(rt_id).unwrap().0, "かんじ");
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn test_merge_adjacent_ruby() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>()
+            .insert_resource(FuriganaSettings {
+                merge_adjacent_ruby: true,
+                ..default()
+            });
+
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::default(),
+                children![
+                    (TextSpan::new("東"), Ruby::new("とう")),
+                    (TextSpan::new("京"), Ruby::new("きょう")),
+                    (TextSpan::new("と")),
+                    (TextSpan::new("大"), Ruby::new("おお")),
+                    (
+                        TextSpan::new("阪"),
+                        Ruby {
+                            position: crate::RubyPosition::Under,
+                            ..Ruby::new("さか")
+                        }
+                    ),
+                ],
+            ))
+            .id();
+        world.flush();
+        world.run_system_once(update_ruby_text).unwrap();
+
+        let spans = world.get::<Children>(text_entity).unwrap().to_vec();
+        assert_eq!(
+            world.get::<MergedRuby>(spans[0]).unwrap().entities(),
+            [spans[1]]
+        );
+        // Differently styled ruby is kept apart
+        let mut ruby_texts = world
+            .query::<&Text>()
+            .iter_many(
+                world,
+                [spans[0], spans[3], spans[4]]
+                    .map(|span| world.get::<LinkedRubyText>(span).unwrap().entity()),
+            )
+            .map(|text| text.0.clone())
+            .collect::<Vec<_>>();
+        ruby_texts.sort();
+        assert_eq!(ruby_texts, ["おお", "さか", "とうきょう"]);
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 3);
+    }
+
+    #[test]
+    fn test_no_ruby_suppresses_ruby_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("text"),
+                NoRuby,
+                Ruby::new("ruby"),
+                children![(TextSpan::new("span"), NoRuby, Ruby::new("span ruby"))],
+            ))
+            .id();
+
+        let world = app.world_mut();
+        assert!(world.get::<LinkedRubyText>(text_entity).is_none());
+        assert_eq!(world.query::<&RubyText>().iter(world).count(), 0);
+        assert_eq!(world.get::<Ruby>(text_entity).unwrap().rt, "ruby");
+    }
+
+    #[test]
+    fn test_furigana_disabled_hides_ruby_of_one_root() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let section_rect = Rect::new(10.0, 5.0, 50.0, 25.0);
+        let [(disabled_text, disabled_rt), (_, enabled_rt)] = [(); 2].map(|_| {
+            spawn_laid_out_text(
+                app.world_mut(),
+                ComputedNode {
+                    size: Vec2::new(60.0, 30.0),
+                    ..default()
+                },
+                Vec2::new(100.0, 50.0),
+                section_rect,
+            )
+        });
+        app.world_mut()
+            .entity_mut(disabled_text)
+            .insert(FuriganaDisabled);
+
+        let world = app.world_mut();
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(
+            world.get::<Visibility>(disabled_rt),
+            Some(&Visibility::Hidden)
+        );
+        assert_eq!(
+            world.get::<Visibility>(enabled_rt),
+            Some(&Visibility::Inherited)
+        );
+
+        world.entity_mut(disabled_text).remove::<FuriganaDisabled>();
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(
+            world.get::<Visibility>(disabled_rt),
+            Some(&Visibility::Inherited)
+        );
+    }
+
+    #[test]
+    fn test_cjk_only_hides_ruby_on_latin_text() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let section_rect = Rect::new(10.0, 5.0, 50.0, 25.0);
+        let [(_, cjk_rt), (latin_text, latin_rt)] = [(); 2].map(|_| {
+            spawn_laid_out_text(
+                app.world_mut(),
+                ComputedNode {
+                    size: Vec2::new(60.0, 30.0),
+                    ..default()
+                },
+                Vec2::new(100.0, 50.0),
+                section_rect,
+            )
+        });
+        let world = app.world_mut();
+        world.entity_mut(latin_text).insert(Text::new("Lorem"));
+
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(
+            world.get::<Visibility>(latin_rt),
+            Some(&Visibility::Inherited)
+        );
+
+        world.resource_mut::<FuriganaSettings>().cjk_only = true;
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(world.get::<Visibility>(latin_rt), Some(&Visibility::Hidden));
+        assert_eq!(
+            world.get::<Visibility>(cjk_rt),
+            Some(&Visibility::Inherited)
+        );
+    }
+
+    #[test]
+    fn test_ruby_follows_base_visibility_and_display() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        let parent = app.world().get::<ChildOf>(text_entity).unwrap().parent();
+
+        let world = app.world_mut();
+        world.entity_mut(parent).insert(Visibility::Hidden);
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(world.get::<Visibility>(rt_id), Some(&Visibility::Hidden));
+
+        // The base's own visibility overrides that of its parent
+        world.entity_mut(text_entity).insert(Visibility::Visible);
+        world.run_system_once(update_ruby).unwrap();
+        assert_eq!(world.get::<Visibility>(rt_id), Some(&Visibility::Inherited));
+
+        world.get_mut::<Node>(text_entity).unwrap().display = Display::None;
+        world.run_system_once(update_ruby_display).unwrap();
+        assert_eq!(world.get::<Node>(rt_id).unwrap().display, Display::None);
+
+        world.get_mut::<Node>(text_entity).unwrap().display = Display::Flex;
+        world.run_system_once(update_ruby_display).unwrap();
+        assert_eq!(world.get::<Node>(rt_id).unwrap().display, Display::Flex);
+    }
+
+    #[test]
+    fn test_ruby_placement_at_scale_factor() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+        // Everything below is in physical pixels at a scale factor of 2
+        let computed_node = |size| ComputedNode {
+            size,
+            inverse_scale_factor: 0.5,
+            ..default()
+        };
+        let parent = world
+            .spawn((
+                Node::default(),
+                computed_node(Vec2::new(400.0, 200.0)),
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(200.0, 100.0))),
+            ))
+            .id();
+
+        for parent in [Some(parent), None] {
+            let text_entity = world.spawn((Text::new("漢字"), Ruby::new("かんじ"))).id();
+            if let Some(parent) = parent {
+                world.entity_mut(text_entity).insert(ChildOf(parent));
+            }
+            world.entity_mut(text_entity).insert((
+                computed_node(Vec2::new(120.0, 60.0)),
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(200.0, 100.0))),
+                TextLayoutInfo {
+                    scale_factor: 2.0,
+                    glyphs: vec![],
+                    section_rects: vec![(text_entity, Rect::new(20.0, 10.0, 100.0, 50.0))],
+                    size: Vec2::new(120.0, 60.0),
+                },
+            ));
+            let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+            world
+                .entity_mut(rt_id)
+                .insert(computed_node(Vec2::new(60.0, 20.0)));
+
+            world.run_system_once(update_ruby).unwrap();
+
+            // In logical pixels, relative to the parent, or to the viewport for a root node that
+            // fills the same area
+            let node = world.get::<Node>(rt_id).unwrap();
+            assert_eq!(
+                (node.left, node.top),
+                (Val::Px(85.0), Val::Px(35.0)),
+                "{parent:?}"
+            );
+            let global = world.get::<UiGlobalTransform>(rt_id).unwrap();
+            assert_eq!(global.translation, Vec2::new(200.0, 80.0));
+        }
+    }
+
+    #[test]
+    fn test_ruby_over_wrapping_flex_items() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+
+        let row = world
+            .spawn((
+                Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    ..default()
+                },
+                ComputedNode {
+                    size: Vec2::new(100.0, 60.0),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(50.0, 30.0))),
+            ))
+            .id();
+
+        // Each annotated item is its own text root, the third one wrapped to the second row
+        let rt_ids = [
+            Vec2::new(20.0, 10.0),
+            Vec2::new(60.0, 10.0),
+            Vec2::new(20.0, 40.0),
+        ]
+        .map(|item_center| {
+            let item = world
+                .spawn((Text::new("漢字"), Ruby::new("かんじ"), ChildOf(row)))
+                .id();
+            let section_rect = Rect::new(0.0, 0.0, 40.0, 20.0);
+            world.entity_mut(item).insert((
+                ComputedNode {
+                    size: section_rect.size(),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(item_center)),
+                TextLayoutInfo {
+                    scale_factor: 1.0,
+                    glyphs: vec![],
+                    section_rects: vec![(item, section_rect)],
+                    size: section_rect.size(),
+                },
+            ));
+            let rt_id = world.get::<LinkedRubyText>(item).unwrap().entity();
+            world.entity_mut(rt_id).insert(ComputedNode {
+                size: Vec2::new(30.0, 10.0),
+                ..default()
+            });
+            rt_id
+        });
+        world.run_system_once(update_ruby).unwrap();
+
+        let positions = rt_ids.map(|rt_id| {
+            let node = world.get::<Node>(rt_id).unwrap();
+            (node.left, node.top)
+        });
+        assert_eq!(
+            positions,
+            [
+                (Val::Px(5.0), Val::Px(-5.0)),
+                (Val::Px(45.0), Val::Px(-5.0)),
+                (Val::Px(5.0), Val::Px(25.0)),
+            ]
+        );
+        // Ruby text is kept out of the flex flow
+        assert!(rt_ids.iter().all(|&rt_id| {
+            world.get::<ChildOf>(rt_id).map(ChildOf::parent) == Some(row)
+                && world.get::<Node>(rt_id).unwrap().position_type == PositionType::Absolute
+        }));
+    }
+
+    #[test]
+    fn test_ruby_over_grid_items() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+
+        // 2x2 grid of 50x30 cells inside a 5px border, with its top-left at the origin
+        let grid = world
+            .spawn((
+                Node {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::px(2, 50.0),
+                    grid_template_rows: RepeatedGridTrack::px(2, 30.0),
+                    border: UiRect::all(px(5)),
+                    ..default()
+                },
+                ComputedNode {
+                    size: Vec2::new(110.0, 70.0),
+                    border: 5.0.into(),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(55.0, 35.0))),
+            ))
+            .id();
+
+        // A 40x20 text centered in each cell
+        let rt_ids = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(column, row)| {
+            let item = world
+                .spawn((
+                    Text::new("漢字"),
+                    Ruby::new("かんじ"),
+                    Node {
+                        justify_self: JustifySelf::Center,
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    },
+                    ChildOf(grid),
+                ))
+                .id();
+            let section_rect = Rect::new(0.0, 0.0, 40.0, 20.0);
+            world.entity_mut(item).insert((
+                ComputedNode {
+                    size: section_rect.size(),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(
+                    30.0 + column * 50.0,
+                    20.0 + row * 30.0,
+                ))),
+                TextLayoutInfo {
+                    scale_factor: 1.0,
+                    glyphs: vec![],
+                    section_rects: vec![(item, section_rect)],
+                    size: section_rect.size(),
+                },
+            ));
+            let rt_id = world.get::<LinkedRubyText>(item).unwrap().entity();
+            world.entity_mut(rt_id).insert(ComputedNode {
+                size: Vec2::new(30.0, 10.0),
+                ..default()
+            });
+            rt_id
+        });
+        world.run_system_once(update_ruby).unwrap();
+
+        // Relative to the padding box of the grid, over the text of each cell
+        let positions = rt_ids.map(|rt_id| {
+            let node = world.get::<Node>(rt_id).unwrap();
+            (node.left, node.top)
+        });
+        assert_eq!(
+            positions,
+            [
+                (Val::Px(10.0), Val::Px(0.0)),
+                (Val::Px(60.0), Val::Px(0.0)),
+                (Val::Px(10.0), Val::Px(30.0)),
+                (Val::Px(60.0), Val::Px(30.0)),
+            ]
+        );
+        // Ruby text takes no grid cell of its own
+        assert!(rt_ids.iter().all(|&rt_id| {
+            let node = world.get::<Node>(rt_id).unwrap();
+            node.position_type == PositionType::Absolute
+                && node.grid_row == GridPlacement::default()
+                && node.grid_column == GridPlacement::default()
+        }));
+    }
+
+    #[test]
+    fn test_ruby_stabilized() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        let frame = |world: &mut World| {
+            world.run_system_once(update_ruby).unwrap();
+            world.run_system_once(track_ruby_settling).unwrap();
+            world
+                .resource_mut::<Messages<RubyStabilized>>()
+                .drain()
+                .collect::<Vec<_>>()
+        };
+
+        // Placed, then unchanged in the next frame
+        assert_eq!(frame(app.world_mut()), []);
+        assert_eq!(
+            frame(app.world_mut()),
+            [RubyStabilized {
+                base: text_entity,
+                ruby: rt_id
+            }]
+        );
+        // Only once
+        assert_eq!(frame(app.world_mut()), []);
+    }
+
+    #[test]
+    fn test_ruby_text_spawned_and_positioned() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        let world = app.world_mut();
+        assert_eq!(
+            world
+                .resource_mut::<Messages<RubyTextSpawned>>()
+                .drain()
+                .collect::<Vec<_>>(),
+            [RubyTextSpawned {
+                base: text_entity,
+                ruby_text: rt_id
+            }]
+        );
+
+        let frame = |world: &mut World| {
+            world.run_system_once(update_ruby).unwrap();
+            world.run_system_once(track_ruby_settling).unwrap();
+            world
+                .resource_mut::<Messages<RubyPositioned>>()
+                .drain()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            frame(world),
+            [RubyPositioned {
+                base: text_entity,
+                ruby_text: rt_id
+            }]
+        );
+        assert_eq!(frame(world), []);
+    }
+
+    #[test]
+    fn test_subpixel_nudge() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        app.insert_resource(FuriganaSettings {
+            subpixel_nudge: Vec2::new(0.5, -0.25),
+            ..default()
+        });
+
+        let (_, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        app.world_mut().run_system_once(update_ruby).unwrap();
+
+        let node = app.world().get::<Node>(rt_id).unwrap();
+        assert_eq!((node.left, node.top), (Val::Px(85.5), Val::Px(34.75)));
+    }
+
+    #[test]
+    fn test_ruby_translation_under_base() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        app.world_mut()
+            .entity_mut(text_entity)
+            .insert(RubyTranslation::new("kanji"));
+        let translation_id = app
+            .world()
+            .get::<LinkedRubyTranslationText>(text_entity)
+            .unwrap()
+            .entity();
+        app.world_mut()
+            .entity_mut(translation_id)
+            .insert(ComputedNode {
+                size: Vec2::new(30.0, 10.0),
+                ..default()
+            });
+        app.world_mut().run_system_once(update_ruby).unwrap();
+
+        assert_eq!(app.world().get::<Text>(translation_id).unwrap().0, "kanji");
+        let reading = app.world().get::<Node>(rt_id).unwrap();
+        assert_eq!((reading.left, reading.top), (Val::Px(85.0), Val::Px(35.0)));
+        let translation = app.world().get::<Node>(translation_id).unwrap();
+        assert_eq!(
+            (translation.left, translation.top),
+            (Val::Px(85.0), Val::Px(55.0))
+        );
+    }
+
+    #[test]
+    fn test_ruby_stack_stacks_away_from_base() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        app.world_mut().entity_mut(text_entity).insert((
+            RubyTranslation::new("kanji"),
+            RubyStack(vec![
+                Ruby::new("x"),
+                Ruby::new("y").under(),
+                Ruby::new("z").under(),
+            ]),
+        ));
+        let translation_id = app
+            .world()
+            .get::<LinkedRubyTranslationText>(text_entity)
+            .unwrap()
+            .entity();
+        let stack_ids = app
+            .world_mut()
+            .run_system_once(move |links: crate::RubyLinks| {
+                [0, 1, 2].map(|index| links.stack_text(text_entity, index).unwrap())
+            })
+            .unwrap();
+        for id in core::iter::once(translation_id).chain(stack_ids) {
+            app.world_mut().entity_mut(id).insert(ComputedNode {
+                size: Vec2::new(30.0, 10.0),
+                ..default()
+            });
+        }
+        app.world_mut().run_system_once(update_ruby).unwrap();
+
+        let top = |id| app.world().get::<Node>(id).unwrap().top;
+        assert_eq!(app.world().get::<Text>(stack_ids[2]).unwrap().0, "z");
+        // Outward of the reading over the base and of the translation under it, in order
+        assert_eq!(top(rt_id), Val::Px(35.0));
+        assert_eq!(top(stack_ids[0]), Val::Px(25.0));
+        assert_eq!(top(translation_id), Val::Px(55.0));
+        assert_eq!(top(stack_ids[1]), Val::Px(65.0));
+        assert_eq!(top(stack_ids[2]), Val::Px(75.0));
+    }
+
+    #[test]
+    fn test_ruby_stack_respawns_on_resize() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("犬"),
+                RubyStack(vec![Ruby::new("いぬ"), Ruby::new("dog").under()]),
+            ))
+            .id();
+        let stack_texts = |app: &mut App| {
+            let world = app.world_mut();
+            let mut texts = world
+                .query::<(&RubyStackText, &Text)>()
+                .iter(world)
+                .map(|(stack_text, text)| {
+                    assert_eq!(stack_text.base, text_entity);
+                    (stack_text.index, text.0.clone())
+                })
+                .collect::<Vec<_>>();
+            texts.sort();
+            texts
+        };
+        assert_eq!(
+            stack_texts(&mut app),
+            [(0, "いぬ".to_owned()), (1, "dog".to_owned())]
+        );
+        let resize = |app: &mut App| {
+            app.world_mut()
+                .run_system_cached(crate::resize_ruby_stacks)
+                .unwrap();
+        };
+        resize(&mut app);
+        assert_eq!(stack_texts(&mut app).len(), 2);
+
+        app.world_mut()
+            .get_mut::<RubyStack>(text_entity)
+            .unwrap()
+            .0
+            .push(Ruby::new("canine").under());
+        resize(&mut app);
+        assert_eq!(
+            stack_texts(&mut app),
+            [
+                (0, "いぬ".to_owned()),
+                (1, "dog".to_owned()),
+                (2, "canine".to_owned())
+            ]
+        );
+
+        app.world_mut()
+            .get_mut::<RubyStack>(text_entity)
+            .unwrap()
+            .0
+            .truncate(1);
+        resize(&mut app);
+        assert_eq!(stack_texts(&mut app), [(0, "いぬ".to_owned())]);
+
+        app.world_mut()
+            .entity_mut(text_entity)
+            .remove::<RubyStack>();
+        app.world_mut().flush();
+        assert!(stack_texts(&mut app).is_empty());
+        assert!(
+            app.world()
+                .get::<LinkedRubyStackTexts>(text_entity)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_ruby_follows_translated_text_node() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        app.world_mut().run_system_once(update_ruby).unwrap();
+        let node = app.world().get::<Node>(rt_id).unwrap();
+        assert_eq!((node.left, node.top), (Val::Px(85.0), Val::Px(35.0)));
+
+        // Translated as UI layout would with `UiTransform::translation`
+        app.world_mut().entity_mut(text_entity).insert((
+            UiTransform::from_translation(Val2::px(20.0, 10.0)),
+            UiGlobalTransform::from(Affine2::from_translation(Vec2::new(120.0, 60.0))),
+        ));
+        app.world_mut().run_system_once(update_ruby).unwrap();
+
+        let node = app.world().get::<Node>(rt_id).unwrap();
+        assert_eq!((node.left, node.top), (Val::Px(105.0), Val::Px(45.0)));
+        // Not applied twice
+        assert_eq!(
+            app.world().get::<UiTransform>(rt_id).unwrap().translation,
+            Val2::ZERO
+        );
+    }
+
+    #[test]
+    fn test_static_ruby_is_not_repositioned() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+        let world = app.world_mut();
+        let update_ruby = world.register_system(update_ruby);
+        world.run_system(update_ruby).unwrap();
+        let last_changed = |world: &World| {
+            let ruby_text = world.entity(rt_id);
+            (
+                ruby_text.get_change_ticks::<Node>().unwrap().changed,
+                ruby_text.get_change_ticks::<UiTransform>().unwrap().changed,
+            )
+        };
+        let placed = last_changed(world);
+
+        world.run_system(update_ruby).unwrap();
+        assert_eq!(last_changed(world), placed);
+
+        // Still follows the base once it moves
+        world
+            .entity_mut(text_entity)
+            .insert(UiGlobalTransform::from(Affine2::from_translation(
+                Vec2::new(120.0, 60.0),
+            )));
+        world.run_system(update_ruby).unwrap();
+        let node = world.get::<Node>(rt_id).unwrap();
+        assert_eq!((node.left, node.top), (Val::Px(105.0), Val::Px(45.0)));
+    }
+
+    #[test]
+    fn test_ruby_follows_span_insertion_and_removal() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+
+        let text_entity = world
+            .spawn((
+                Text::default(),
+                ComputedNode {
+                    size: Vec2::new(100.0, 30.0),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(50.0, 15.0))),
+            ))
+            .id();
+        let span = world
+            .spawn((
+                TextSpan::new("漢字"),
+                Ruby::new("かんじ"),
+                ChildOf(text_entity),
+            ))
+            .id();
+        let rt_id = world.get::<LinkedRubyText>(span).unwrap().entity();
+        world.entity_mut(rt_id).insert(ComputedNode {
+            size: Vec2::new(20.0, 10.0),
+            ..default()
+        });
+
+        let lay_out = |world: &mut World, section_rects: Vec<(Entity, Rect)>| {
+            world.entity_mut(text_entity).insert(TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects,
+                size: Vec2::new(100.0, 30.0),
+            });
+            world.run_system_once(update_ruby).unwrap();
+            world.get::<Node>(rt_id).unwrap().left
+        };
+
+        assert_eq!(
+            lay_out(world, vec![(span, Rect::new(0.0, 0.0, 40.0, 30.0))]),
+            Val::Px(10.0)
+        );
+
+        // A span inserted before the annotated one shifts it
+        let inserted = world.spawn(TextSpan::new("前")).id();
+        world
+            .entity_mut(text_entity)
+            .insert_children(0, &[inserted]);
+        assert_eq!(
+            lay_out(
+                world,
+                vec![
+                    (inserted, Rect::new(0.0, 0.0, 20.0, 30.0)),
+                    (span, Rect::new(20.0, 0.0, 60.0, 30.0)),
+                ],
+            ),
+            Val::Px(30.0)
+        );
+
+        world.entity_mut(inserted).despawn();
+        assert_eq!(
+            lay_out(world, vec![(span, Rect::new(0.0, 0.0, 40.0, 30.0))]),
+            Val::Px(10.0)
+        );
+    }
+
+    #[test]
+    fn test_ruby_follows_visually_reordered_spans() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+
+        let text_entity = world
+            .spawn((
+                Text::default(),
+                ComputedNode {
+                    size: Vec2::new(100.0, 30.0),
+                    ..default()
+                },
+                UiGlobalTransform::from(Affine2::from_translation(Vec2::new(50.0, 15.0))),
+            ))
+            .id();
+        let spans = [("東", "ひがし"), ("西", "にし")].map(|(text, rt)| {
+            let span = world
+                .spawn((TextSpan::new(text), Ruby::new(rt), ChildOf(text_entity)))
+                .id();
+            let rt_id = world.get::<LinkedRubyText>(span).unwrap().entity();
+            world.entity_mut(rt_id).insert(ComputedNode {
+                size: Vec2::new(20.0, 10.0),
+                ..default()
+            });
+            (span, rt_id)
+        });
+
+        // Laid out in the reverse order of the hierarchy
+        world.entity_mut(text_entity).insert(TextLayoutInfo {
+            scale_factor: 1.0,
+            glyphs: vec![],
+            section_rects: vec![
+                (spans[1].0, Rect::new(0.0, 0.0, 20.0, 30.0)),
+                (spans[0].0, Rect::new(20.0, 0.0, 40.0, 30.0)),
+            ],
+            size: Vec2::new(100.0, 30.0),
+        });
+        world.run_system_once(update_ruby).unwrap();
+
+        assert_eq!(world.get::<Node>(spans[0].1).unwrap().left, Val::Px(20.0));
+        assert_eq!(world.get::<Node>(spans[1].1).unwrap().left, Val::Px(0.0));
+    }
+
+    #[test]
+    fn test_ruby_window_rect() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let world = app.world_mut();
+        let text_entity = world.spawn((Text::new("漢字"), Ruby::new("かんじ"))).id();
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        world.entity_mut(rt_id).insert((
+            ComputedNode {
+                size: Vec2::new(30.0, 10.0),
+                inverse_scale_factor: 0.5,
+                ..default()
+            },
+            UiGlobalTransform::from(Affine2::from_translation(Vec2::new(100.0, 50.0))),
+        ));
+
+        // Not placed yet
+        assert_eq!(crate::ruby_window_rect(world, text_entity), None);
+
+        world.entity_mut(rt_id).insert(Visibility::Inherited);
+        assert_eq!(
+            crate::ruby_window_rect(world, text_entity),
+            Some(Rect::new(42.5, 22.5, 57.5, 27.5))
+        );
+        assert_eq!(crate::translation_window_rect(world, text_entity), None);
+
+        // The translation text is separate from the ruby text
+        world
+            .entity_mut(text_entity)
+            .insert(RubyTranslation(Ruby::new("Chinese characters")));
+        let translation_id = world
+            .get::<LinkedRubyTranslationText>(text_entity)
+            .unwrap()
+            .entity();
+        world.entity_mut(translation_id).insert((
+            ComputedNode {
+                size: Vec2::new(60.0, 10.0),
+                inverse_scale_factor: 0.5,
+                ..default()
+            },
+            UiGlobalTransform::from(Affine2::from_translation(Vec2::new(100.0, 90.0))),
+            Visibility::Inherited,
+        ));
+        assert_eq!(
+            crate::translation_window_rect(world, text_entity),
+            Some(Rect::new(35.0, 42.5, 65.0, 47.5))
+        );
+        assert_eq!(
+            crate::ruby_window_rect(world, text_entity),
+            Some(Rect::new(42.5, 22.5, 57.5, 27.5))
+        );
+    }
+
+    #[test]
+    fn test_compute_placement_now() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let section_rect = Rect::new(10.0, 5.0, 50.0, 25.0);
+        let (text_entity, _) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            section_rect,
+        );
+
+        let placement = crate::compute_placement_now(app.world_mut(), text_entity).unwrap();
+        assert_eq!(
+            placement,
+            RubyPlacement {
+                base_rect: section_rect,
+                ruby_rect: Rect::new(15.0, 0.0, 45.0, 10.0),
+                ruby_center: Vec3::new(100.0, 40.0, 0.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_compute_placement_now_forces_layout() {
+        let mut app = ui_layout_app();
+        let world = app.world_mut();
+
+        // Neither the text nor its ruby text is laid out yet
+        let text_entity = world
+            .spawn((
+                Text::new("AA"),
+                TextFont::from_font_size(20.0),
+                Ruby::new("aa"),
+                Node {
+                    margin: UiRect::all(px(30.0)),
+                    ..default()
+                },
+            ))
+            .id();
+        let placement = crate::compute_placement_now(world, text_entity).unwrap();
+
+        // Where the ruby text is then placed and laid out
+        world.run_system_cached(update_ruby).unwrap();
+        lay_out_ui(world);
+        let rt_id = world.get::<LinkedRubyText>(text_entity).unwrap().entity();
+        let rt_center = world.get::<UiGlobalTransform>(rt_id).unwrap().translation;
+        assert!(rt_center.abs_diff_eq(placement.ruby_center.truncate(), 0.01));
+    }
+
+    #[test]
+    fn test_compute_placement_now_of_merged_ruby() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .insert_resource(FuriganaSettings {
+                merge_adjacent_ruby: true,
+                ..default()
+            });
+
+        let world = app.world_mut();
+        world.init_resource::<Assets<Font>>();
+        let text_entity = world
+            .spawn((
+                Text::default(),
+                children![
+                    (TextSpan::new("東"), Ruby::new("とう")),
+                    (TextSpan::new("京"), Ruby::new("きょう")),
+                ],
+            ))
+            .id();
+        world.flush();
+        let spans = world.get::<Children>(text_entity).unwrap().to_vec();
+        world.entity_mut(text_entity).insert((
+            ComputedNode {
+                size: Vec2::new(40.0, 20.0),
+                ..default()
+            },
+            UiGlobalTransform::from(Affine2::from_translation(Vec2::new(20.0, 10.0))),
+            TextLayoutInfo {
+                scale_factor: 1.0,
+                glyphs: vec![],
+                section_rects: vec![
+                    (spans[0], Rect::new(0.0, 0.0, 20.0, 20.0)),
+                    (spans[1], Rect::new(20.0, 0.0, 40.0, 20.0)),
+                ],
+                size: Vec2::new(40.0, 20.0),
+            },
+        ));
+        let rt_id = world.get::<LinkedRubyText>(spans[0]).unwrap().entity();
+        world.entity_mut(rt_id).insert(ComputedNode {
+            size: Vec2::new(50.0, 10.0),
+            ..default()
+        });
+
+        // Over both merged spans
+        assert_eq!(
+            crate::compute_placement_now(world, spans[0]),
+            Some(RubyPlacement {
+                base_rect: Rect::new(0.0, 0.0, 40.0, 20.0),
+                ruby_rect: Rect::new(-5.0, -5.0, 45.0, 5.0),
+                ruby_center: Vec3::new(20.0, 0.0, 0.0),
+            })
+        );
+
+        world.entity_mut(text_entity).insert(FuriganaDisabled);
+        assert_eq!(crate::compute_placement_now(world, spans[0]), None);
+    }
+
+    #[test]
+    fn test_debug_placement() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let (text_entity, _) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            Rect::new(10.0, 5.0, 50.0, 25.0),
+        );
+
+        let debug = crate::debug_placement(app.world_mut(), text_entity);
+        assert!(debug.starts_with("Ruby \"かんじ\""), "{debug}");
+        assert!(
+            debug.contains("position: Over, align: Center, mode: Group"),
+            "{debug}"
+        );
+        assert!(
+            debug
+                .contains("placed ruby rect: Rect { min: Vec2(15.0, 0.0), max: Vec2(45.0, 10.0) }"),
+            "{debug}"
+        );
+
+        let plain = app.world_mut().spawn(Text::new("plain")).id();
+        assert!(crate::debug_placement(app.world_mut(), plain).ends_with("has no Ruby"));
+    }
+
+    #[test]
+    fn test_clearing_ruby_color_reverts_to_base_color() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let red = Color::srgb(1.0, 0.0, 0.0);
+        let blue = Color::srgb(0.0, 0.0, 1.0);
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("text"),
+                TextColor(red),
+                Ruby {
+                    color: Some(TextColor(blue)),
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText>(text_entity)
+            .unwrap()
+            .entity();
+
+        app.world_mut().run_system_once(update_ruby_text).unwrap();
+        assert_eq!(app.world().get::<TextColor>(rt_id).unwrap().0, blue);
+
+        app.world_mut().get_mut::<Ruby>(text_entity).unwrap().color = None;
+        app.world_mut().run_system_once(update_ruby_text).unwrap();
+        assert_eq!(app.world().get::<TextColor>(rt_id).unwrap().0, red);
+    }
+
+    #[test]
+    fn test_ruby_palette_color() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let red = Color::srgb(1.0, 0.0, 0.0);
+        let green = Color::srgb(0.0, 1.0, 0.0);
+        let blue = Color::srgb(0.0, 0.0, 1.0);
+        let world = app.world_mut();
+        world.insert_resource(crate::RubyPalette(vec![red, green]));
+        let rt_ids = [0, 1].map(|index| {
+            let text_entity = world
+                .spawn((
+                    Text::new("text"),
+                    Ruby {
+                        color: Some(TextColor(blue)),
+                        color_ref: Some(crate::RubyColorRef::Palette(index)),
+                        ..Ruby::new("ruby")
+                    },
+                ))
+                .id();
+            world.get::<LinkedRubyText>(text_entity).unwrap().entity()
+        });
+        let colors = |world: &mut World| {
+            world.run_system_once(update_ruby_text).unwrap();
+            rt_ids.map(|rt_id| world.get::<TextColor>(rt_id).unwrap().0)
+        };
+
+        assert_eq!(colors(world), [red, green]);
+
+        world.resource_mut::<crate::RubyPalette>().0 = vec![green];
+        // Falls back to `Ruby::color` for a missing slot
+        assert_eq!(colors(world), [green, blue]);
+    }
+
+    #[test]
+    fn test_span_ruby_color_follows_span() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let red = Color::srgb(1.0, 0.0, 0.0);
+        let blue = Color::srgb(0.0, 0.0, 1.0);
+        let world = app.world_mut();
+        let text_entity = world
+            .spawn((
+                Text::new("text"),
+                children![(TextSpan::new("span"), TextColor(red), Ruby::new("ruby"))],
+            ))
+            .id();
+        world.flush();
+        let span = world.get::<Children>(text_entity).unwrap()[0];
+        let rt_id = world.get::<LinkedRubyText>(span).unwrap().entity();
+        assert_eq!(world.get::<TextColor>(rt_id).unwrap().0, red);
+
+        world.get_mut::<TextColor>(span).unwrap().0 = blue;
+        world.run_system_once(update_ruby_text).unwrap();
+        assert_eq!(world.get::<TextColor>(rt_id).unwrap().0, blue);
+    }
+
+    #[test]
+    fn test_ruby_font_follows_ruby() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let ruby_font = bevy::asset::uuid_handle!("7c1d2e4f-0000-4000-8000-000000000001");
+        let text_entity = app
+            .world_mut()
+            .spawn((
+                Text::new("text"),
+                Ruby {
+                    font: Some(ruby_font.clone()),
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+        let rt_id = app
+            .world()
+            .get::<LinkedRubyText>(text_entity)
+            .unwrap()
+            .entity();
+        assert_eq!(app.world().get::<TextFont>(rt_id).unwrap().font, ruby_font);
+
+        // Falls back to the font of the base text
+        app.world_mut().get_mut::<Ruby>(text_entity).unwrap().font = None;
+        app.world_mut().run_system_once(update_ruby_text).unwrap();
+        assert_eq!(
+            app.world().get::<TextFont>(rt_id).unwrap().font,
+            TextFont::default().font
+        );
+    }
+
+    #[test]
+    fn test_inherited_ruby_color_follows_base_alpha() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin)
+            .init_resource::<Time>();
+
+        let world = app.world_mut();
+        let inherited = world.spawn((Text::new("text"), Ruby::new("ruby"))).id();
+        let explicit = world
+            .spawn((
+                Text::new("text"),
+                Ruby {
+                    color: Some(TextColor(Color::WHITE.with_alpha(0.5))),
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+        let opaque = world
+            .spawn((
+                Text::new("text"),
+                Ruby {
+                    color: Some(TextColor(Color::WHITE)),
+                    inherit_alpha: false,
+                    ..Ruby::new("ruby")
+                },
+            ))
+            .id();
+
+        for alpha in [1.0, 0.5, 0.0] {
+            for base in [inherited, explicit, opaque] {
+                world.get_mut::<TextColor>(base).unwrap().0 = Color::WHITE.with_alpha(alpha);
+            }
+            world.run_system_once(update_ruby_text).unwrap();
+
+            let ruby_alpha = |base| {
+                let rt_id = world.get::<LinkedRubyText>(base).unwrap().entity();
+                world.get::<TextColor>(rt_id).unwrap().0.alpha()
+            };
+            assert_eq!(ruby_alpha(inherited), alpha);
+            assert_eq!(ruby_alpha(explicit), 0.5 * alpha);
+            // Independent of the base without `inherit_alpha`
+            assert_eq!(ruby_alpha(opaque), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_ruby_hidden_until_placed() {
+        let mut app = App::new();
+        app.add_plugins(crate::FuriganaPlugin);
+
+        let section_rect = Rect::new(10.0, 5.0, 50.0, 25.0);
+        let (text_entity, rt_id) = spawn_laid_out_text(
+            app.world_mut(),
+            ComputedNode {
+                size: Vec2::new(60.0, 30.0),
+                ..default()
+            },
+            Vec2::new(100.0, 50.0),
+            section_rect,
+        );
+        assert_eq!(
+            app.world().get::<Visibility>(rt_id),
+            Some(&Visibility::Hidden)
+        );
+
+        // Font not loaded yet
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(text_entity)
+            .unwrap()
+            .section_rects
+            .clear();
+        app.world_mut().run_system_once(update_ruby).unwrap();
+        assert_eq!(
+            app.world().get::<Visibility>(rt_id),
+            Some(&Visibility::Hidden)
+        );
+
+        app.world_mut()
+            .get_mut::<TextLayoutInfo>(text_entity)
+            .unwrap()
+            .section_rects
+            .push((text_entity, section_rect));
+        app.world_mut().run_system_once(update_ruby).unwrap();
+        assert_eq!(
+            app.world().get::<Visibility>(rt_id),
+            Some(&Visibility::Inherited)
+        );
+    }
 }